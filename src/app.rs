@@ -1,7 +1,9 @@
 use crate::app_modes;
+use crate::bag::BagPlayer;
 use crate::config::TermvizConfig;
 use crate::footprint::get_footprint;
 use crate::listeners::Listeners;
+use crate::transport::Transport;
 use crossterm::{
     event::EnableMouseCapture,
     execute,
@@ -11,6 +13,7 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::io;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
 use tui::backend::Backend;
@@ -26,49 +29,123 @@ pub struct App<B: Backend> {
     show_help: bool,
     keymap: HashMap<String, String>,
     app_modes: Vec<Box<dyn app_modes::BaseMode<B>>>,
+    /// Number of the playback mode, which has its own key as it only exists with a bag.
+    playback_mode: Option<usize>,
 }
 
 impl<B: Backend> App<B> {
-    pub fn new(tf_listener: Arc<rustros_tf::TfListener>, config: TermvizConfig) -> App<B> {
-        let config_copy = config.clone();
+    pub fn new(
+        transport: Arc<dyn Transport>,
+        config: TermvizConfig,
+        config_path: PathBuf,
+        player: Option<BagPlayer>,
+    ) -> App<B> {
         let listeners = Listeners::new(
-            tf_listener.clone(),
+            transport.clone(),
             config.fixed_frame.clone(),
-            config.laser_topics,
-            config.marker_topics,
-            config.marker_array_topics,
-            config.map_topics,
-            config.pose_stamped_topics,
-            config.pose_array_topics,
-            config.pointcloud2_topics,
-            config.path_topics,
+            config.laser_topics.clone(),
+            config.marker_topics.clone(),
+            config.marker_array_topics.clone(),
+            config.map_topics.clone(),
+            config.pose_stamped_topics.clone(),
+            config.pose_array_topics.clone(),
+            config.pointcloud2_topics.clone(),
+            config.path_topics.clone(),
         );
+        App::with_listeners(
+            transport,
+            config,
+            config_path,
+            listeners,
+            &get_footprint(),
+            size().unwrap(),
+            player,
+        )
+    }
+
+    /// Creates the app from listeners that were already built.
+    ///
+    /// This does not query the terminal nor the ROS parameters, which allows to
+    /// render the app on any backend and transport, e.g. in tests.
+    /// The playback mode is only added if a bag player is given.
+    pub fn with_listeners(
+        transport: Arc<dyn Transport>,
+        config: TermvizConfig,
+        config_path: PathBuf,
+        listeners: Listeners,
+        footprint: &Vec<(f64, f64)>,
+        terminal_size: (u16, u16),
+        player: Option<BagPlayer>,
+    ) -> App<B> {
+        // config shared by the modes that change it at runtime
+        let shared_config = Rc::new(RefCell::new(config.clone()));
         let viewport = Rc::new(RefCell::new(app_modes::viewport::Viewport::new(
             &config.fixed_frame,
             &config.robot_frame,
-            tf_listener,
+            transport.clone(),
             &config.visible_area,
-            &get_footprint(),
+            footprint,
             config.axis_length,
             config.zoom_factor,
             listeners,
-            size().unwrap(),
+            terminal_size,
+            &config.record,
+            &config.health,
         )));
         let send_pose = Box::new(app_modes::send_pose::SendPose::new(
             &config.send_pose_topic,
             viewport.clone(),
         ));
         let teleop = Box::new(app_modes::teleoperate::Teleoperate::new(
-            viewport,
+            viewport.clone(),
             config.teleop,
         ));
-        let topic_manager = Box::new(app_modes::topic_managment::TopicManager::new(config_copy));
-        let image_view = Box::new(app_modes::image_view::ImageView::new(config.image_topics));
+        let explore = Box::new(app_modes::explore::Explore::new(
+            viewport.clone(),
+            config.explore,
+        ));
+        let marker_namespaces = Box::new(app_modes::marker_namespaces::MarkerNamespaces::new(
+            viewport.clone(),
+            shared_config.clone(),
+            config_path.clone(),
+        ));
+        let layers = Box::new(app_modes::layers::Layers::new(viewport.clone()));
+        let topic_health = Box::new(app_modes::topic_health::TopicHealth::new(viewport.clone()));
+        let topic_manager = Box::new(app_modes::topic_managment::TopicManager::new(
+            shared_config.clone(),
+            config_path,
+            viewport.clone(),
+            transport.clone(),
+        ));
+        let image_view = Box::new(app_modes::image_view::ImageView::new(
+            shared_config,
+            transport,
+        ));
+        let mut modes: Vec<Box<dyn app_modes::BaseMode<B>>> = vec![
+            send_pose,
+            teleop,
+            image_view,
+            topic_manager,
+            explore,
+            marker_namespaces,
+            layers,
+            topic_health,
+        ];
+        let mut playback_mode = None;
+        if let Some(player) = player {
+            playback_mode = Some(modes.len() + 1);
+            modes.push(Box::new(app_modes::playback::Playback::new(
+                viewport,
+                player,
+                config.playback,
+            )));
+        }
         App {
             mode: 1,
             show_help: false,
             keymap: config.key_mapping,
-            app_modes: vec![send_pose, teleop, image_view, topic_manager],
+            app_modes: modes,
+            playback_mode: playback_mode,
         }
     }
 
@@ -117,10 +194,15 @@ impl<B: Backend> App<B> {
                 app_modes::input::MODE_7 => new_mode = 7,
                 app_modes::input::MODE_8 => new_mode = 8,
                 app_modes::input::MODE_9 => new_mode = 9,
+                app_modes::input::PLAYBACK_MODE => new_mode = self.playback_mode.unwrap_or(100),
                 _ => {}
             },
         }
-        if new_mode != 100 && new_mode != self.mode && new_mode <= self.app_modes.len() {
+        if new_mode != 100
+            && new_mode != 0
+            && new_mode != self.mode
+            && new_mode <= self.app_modes.len()
+        {
             self.app_modes[self.mode - 1].reset();
             self.mode = new_mode;
             self.app_modes[self.mode - 1].reset();
@@ -142,8 +224,13 @@ impl<B: Backend> App<B> {
             .iter()
             .enumerate()
             .map(|(i, mode)| {
+                let input = if Some(i + 1) == self.playback_mode {
+                    app_modes::input::PLAYBACK_MODE.to_string()
+                } else {
+                    format!("Switch to mode {}", (i + 1))
+                };
                 [
-                    format!("Switch to mode {}", (i + 1)),
+                    input,
                     "Switch to ".to_string() + &mode.get_name() + &" mode.".to_string(),
                 ]
             })
@@ -165,6 +252,9 @@ impl<B: Backend> App<B> {
             }
         }
         for i in 0..self.app_modes.len() {
+            if Some(i + 1) == self.playback_mode {
+                continue;
+            }
             if key_bindings_raw[i][0].contains("Switch") {
                 key_bindings_raw[i][0] = (i + 1).to_string();
             } else {
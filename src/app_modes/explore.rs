@@ -0,0 +1,258 @@
+//! Explore mode allows to move the camera freely over the map, or to follow any TF frame.
+
+use crate::app_modes::viewport::{UseViewport, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode};
+use crate::config::ExploreConfig;
+use crate::transformation;
+use nalgebra::{Isometry2, Point2, Vector2};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::style::Color;
+use tui::widgets::canvas::{Context, Line};
+
+/// Represents the explore mode.
+pub struct Explore {
+    viewport: Rc<RefCell<Viewport>>,
+    increment: f64,
+    increment_step: f64,
+    camera_pose: Isometry2<f64>,
+    frames: Vec<String>,
+    followed_frame: Option<usize>,
+}
+
+impl Explore {
+    pub fn new(viewport: Rc<RefCell<Viewport>>, config: ExploreConfig) -> Explore {
+        let robot_frame = viewport.borrow().robot_frame.clone();
+        let mut frames = config.follow_frames;
+        if !frames.contains(&robot_frame) {
+            frames.insert(0, robot_frame.clone());
+        }
+        let robot_idx = frames.iter().position(|f| f == &robot_frame);
+        let mut explore = Explore {
+            viewport: viewport,
+            increment: config.default_increment,
+            increment_step: config.increment_step,
+            camera_pose: Isometry2::identity(),
+            frames: frames,
+            followed_frame: robot_idx,
+        };
+        explore.update_followed_frame();
+        explore
+    }
+
+    /// Moves the camera to the pose of the followed frame, if any.
+    fn update_followed_frame(&mut self) {
+        let frame = match self.followed_frame {
+            Some(idx) => &self.frames[idx],
+            None => return,
+        };
+        let res = self.viewport.borrow().transport.lookup_transform(
+            &self.viewport.borrow().static_frame,
+            frame,
+            rosrust::Time::new(),
+        );
+        if let Ok(tf) = res {
+            self.camera_pose = transformation::ros_to_iso2d(&tf.transform);
+        }
+    }
+
+    /// Moves the camera relative to its pan heading and stops following any frame.
+    ///
+    /// The pan heading only sets the direction of the moves, the view itself is not rotated.
+    fn move_camera(&mut self, x: f64, y: f64) {
+        let yaw = self.camera_pose.rotation.angle();
+        let new_x = x * yaw.cos() - y * yaw.sin() + self.camera_pose.translation.x;
+        let new_y = x * yaw.sin() + y * yaw.cos() + self.camera_pose.translation.y;
+        self.camera_pose = Isometry2::new(Vector2::new(new_x, new_y), yaw);
+        self.followed_frame = None;
+    }
+
+    fn turn_pan_heading(&mut self, yaw: f64) {
+        self.camera_pose = Isometry2::new(
+            self.camera_pose.translation.vector,
+            self.camera_pose.rotation.angle() + yaw,
+        );
+    }
+
+    fn follow_robot(&mut self) {
+        let robot_frame = self.viewport.borrow().robot_frame.clone();
+        self.followed_frame = self.frames.iter().position(|f| f == &robot_frame);
+        self.update_followed_frame();
+    }
+
+    fn follow_next_frame(&mut self) {
+        self.followed_frame = match self.followed_frame {
+            Some(idx) => Some((idx + 1) % self.frames.len()),
+            None => Some(0),
+        };
+        self.update_followed_frame();
+    }
+}
+
+impl<B: Backend> BaseMode<B> for Explore {}
+
+impl AppMode for Explore {
+    fn run(&mut self) {
+        self.update_followed_frame();
+    }
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        self.viewport.borrow_mut().handle_input(input);
+        match input.as_str() {
+            input::UP => self.move_camera(self.increment, 0.0),
+            input::DOWN => self.move_camera(-self.increment, 0.0),
+            input::LEFT => self.move_camera(0.0, self.increment),
+            input::RIGHT => self.move_camera(0.0, -self.increment),
+            input::ROTATE_LEFT => self.turn_pan_heading(self.increment),
+            input::ROTATE_RIGHT => self.turn_pan_heading(-self.increment),
+            input::INCREMENT_STEP => self.increment += self.increment_step,
+            input::DECREMENT_STEP => {
+                self.increment = self
+                    .increment_step
+                    .max(self.increment - self.increment_step)
+            }
+            input::CONFIRM => self.follow_robot(),
+            input::CANCEL => self.followed_frame = None,
+            input::FOLLOW_FRAME => self.follow_next_frame(),
+            _ => (),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        "Explore".to_string()
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode allows to move the camera freely over the map, or to follow a TF frame."
+                .to_string(),
+            "The viewport is centered on the camera, which moves along a pan heading shown in gray when it is not following a frame. The view itself is not rotated."
+                .to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = vec![
+            [
+                input::UP.to_string(),
+                "Moves the camera forward along the pan heading.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Moves the camera backward along the pan heading.".to_string(),
+            ],
+            [
+                input::LEFT.to_string(),
+                "Moves the camera to the left of the pan heading.".to_string(),
+            ],
+            [
+                input::RIGHT.to_string(),
+                "Moves the camera to the right of the pan heading.".to_string(),
+            ],
+            [
+                input::ROTATE_LEFT.to_string(),
+                "Turns the pan heading counter-clockwise, without rotating the view.".to_string(),
+            ],
+            [
+                input::ROTATE_RIGHT.to_string(),
+                "Turns the pan heading clockwise, without rotating the view.".to_string(),
+            ],
+            [
+                input::INCREMENT_STEP.to_string(),
+                "Increases the camera step.".to_string(),
+            ],
+            [
+                input::DECREMENT_STEP.to_string(),
+                "Decreases the camera step.".to_string(),
+            ],
+            [
+                input::CONFIRM.to_string(),
+                "Snaps the camera back to the robot and follows it.".to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Stops following the current frame.".to_string(),
+            ],
+            [
+                input::FOLLOW_FRAME.to_string(),
+                "Follows the next frame in the list of frames.".to_string(),
+            ],
+        ];
+        keymap.extend(self.viewport.borrow().get_keymap());
+        keymap
+    }
+}
+
+impl UseViewport for Explore {
+    fn draw_in_viewport(&self, ctx: &mut Context) {
+        self.viewport
+            .borrow()
+            .draw_in_bounds(ctx, self.x_bounds(), self.y_bounds());
+        if self.followed_frame.is_none() {
+            let length = self.viewport.borrow().axis_length;
+            let origin = self.camera_pose * Point2::new(0.0, 0.0);
+            let heading = self.camera_pose * Point2::new(length, 0.0);
+            ctx.draw(&Line {
+                x1: origin.x,
+                y1: origin.y,
+                x2: heading.x,
+                y2: heading.y,
+                color: Color::Gray,
+            });
+            // arrow head
+            for corner in [
+                Point2::new(length / 4.0 * 3.0, length / 4.0),
+                Point2::new(length / 4.0 * 3.0, -length / 4.0),
+            ] {
+                let pt = self.camera_pose * corner;
+                ctx.draw(&Line {
+                    x1: heading.x,
+                    y1: heading.y,
+                    x2: pt.x,
+                    y2: pt.y,
+                    color: Color::Gray,
+                });
+            }
+        }
+    }
+
+    fn x_bounds(&self) -> [f64; 2] {
+        let scale_factor = self.viewport.borrow().terminal_size.0 as f64
+            / self.viewport.borrow().terminal_size.1 as f64
+            * 0.5;
+        [
+            self.camera_pose.translation.x
+                + self.viewport.borrow().initial_bounds[0] / self.viewport.borrow().zoom
+                    * scale_factor,
+            self.camera_pose.translation.x
+                + self.viewport.borrow().initial_bounds[1] / self.viewport.borrow().zoom
+                    * scale_factor,
+        ]
+    }
+
+    fn y_bounds(&self) -> [f64; 2] {
+        [
+            self.camera_pose.translation.y
+                + self.viewport.borrow().initial_bounds[2] / self.viewport.borrow().zoom,
+            self.camera_pose.translation.y
+                + self.viewport.borrow().initial_bounds[3] / self.viewport.borrow().zoom,
+        ]
+    }
+
+    fn info(&self) -> String {
+        let camera_info = match self.followed_frame {
+            Some(idx) => format!(
+                "Following: /{}, Camera step: {:.2}",
+                &self.frames[idx], &self.increment
+            ),
+            None => format!(
+                "Free camera at ({:.2}, {:.2}), Camera step: {:.2}",
+                &self.camera_pose.translation.x, &self.camera_pose.translation.y, &self.increment
+            ),
+        };
+        camera_info + &self.viewport.borrow().info()
+    }
+}
@@ -2,8 +2,13 @@
 
 use self::image::ImageListener;
 use crate::app_modes::{input, AppMode, BaseMode, Drawable};
-use crate::config::ImageListenerConfig;
+use crate::config::TermvizConfig;
 use crate::image;
+use crate::listeners::sync_listeners;
+use crate::transport::Transport;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
 use tui::backend::Backend;
 use tui::layout::{Alignment, Constraint, Layout};
 use tui::style::{Color, Modifier, Style};
@@ -14,24 +19,50 @@ use tui_image::{ColorMode, Image};
 pub struct ImageView {
     images: Vec<ImageListener>,
     active_sub: usize,
+    config: Rc<RefCell<TermvizConfig>>,
+    transport: Arc<dyn Transport>,
 }
 
 /// Represents the image view mode.
 impl ImageView {
-    pub fn new(image_topics: Vec<ImageListenerConfig>) -> ImageView {
+    pub fn new(config: Rc<RefCell<TermvizConfig>>, transport: Arc<dyn Transport>) -> ImageView {
         let mut images: Vec<image::ImageListener> = Vec::new();
-        for image_config in image_topics {
-            images.push(image::ImageListener::new(image_config));
+        for image_config in config.borrow().image_topics.clone() {
+            images.push(image::ImageListener::new(image_config, transport.clone()));
         }
         ImageView {
             images: images,
             active_sub: 0,
+            config: config,
+            transport: transport,
         }
     }
+
+    /// Creates and drops the image listeners to follow the image topics of the configuration,
+    /// which can be changed by the topic manager.
+    fn update_images(&mut self) {
+        let image_topics = self.config.borrow().image_topics.clone();
+        if image_topics
+            .iter()
+            .eq(self.images.iter().map(|i| &i.config))
+        {
+            return;
+        }
+        let transport = self.transport.clone();
+        sync_listeners(
+            &mut self.images,
+            image_topics,
+            |l| &l.config,
+            |l, c| l.set_config(c),
+            |c| image::ImageListener::new(c, transport.clone()),
+        );
+        self.active_sub = self.active_sub.min(self.images.len().saturating_sub(1));
+    }
 }
 
 impl AppMode for ImageView {
     fn run(&mut self) {
+        self.update_images();
         if self.images.len() > 0 && !self.images[self.active_sub].is_active() {
             self.images[self.active_sub].activate();
         }
@@ -0,0 +1,217 @@
+//! Layers mode allows to show, hide, reorder and recolor the layers of the viewport.
+
+use crate::app_modes::viewport::{UseViewport, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::canvas::Context;
+use tui::widgets::{Block, Borders, List, ListItem};
+
+/// Colors that can replace the colors of a layer, in cycling order.
+const PALETTE: [Color; 8] = [
+    Color::Red,
+    Color::Green,
+    Color::Blue,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+    Color::Gray,
+];
+
+/// Represents the layers mode.
+pub struct Layers {
+    viewport: Rc<RefCell<Viewport>>,
+    selected: usize,
+}
+
+impl Layers {
+    pub fn new(viewport: Rc<RefCell<Viewport>>) -> Layers {
+        Layers {
+            viewport: viewport,
+            selected: 0,
+        }
+    }
+
+    fn select(&mut self, offset: i64) {
+        let count = self.viewport.borrow().layers.len() as i64;
+        if count > 0 {
+            self.selected = (self.selected as i64 + offset).rem_euclid(count) as usize;
+        }
+    }
+
+    /// Moves the selected layer by the given offset in the drawing order.
+    fn move_selected(&mut self, offset: i64) {
+        let mut viewport = self.viewport.borrow_mut();
+        let target = self.selected as i64 + offset;
+        if target < 0 || target >= viewport.layers.len() as i64 {
+            return;
+        }
+        viewport.layers.swap(self.selected, target as usize);
+        self.selected = target as usize;
+    }
+
+    /// Replaces the color of the selected layer by the next one of the palette, going back to
+    /// the colors of the listener after the last one.
+    fn cycle_color(&mut self, offset: i64) {
+        let mut viewport = self.viewport.borrow_mut();
+        let layer = match viewport.layers.get_mut(self.selected) {
+            Some(layer) => layer,
+            None => return,
+        };
+        // index PALETTE.len() stands for the colors of the listener
+        let count = PALETTE.len() as i64 + 1;
+        let current = layer
+            .color
+            .and_then(|c| PALETTE.iter().position(|p| *p == c))
+            .unwrap_or(PALETTE.len()) as i64;
+        let next = (current + offset).rem_euclid(count) as usize;
+        layer.color = PALETTE.get(next).copied();
+    }
+}
+
+impl<B: Backend> BaseMode<B> for Layers {}
+
+impl AppMode for Layers {
+    fn run(&mut self) {}
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        self.viewport.borrow_mut().handle_input(input);
+        match input.as_str() {
+            input::UP => self.select(-1),
+            input::DOWN => self.select(1),
+            input::LEFT => self.move_selected(-1),
+            input::RIGHT => self.move_selected(1),
+            input::ROTATE_LEFT => self.cycle_color(-1),
+            input::ROTATE_RIGHT => self.cycle_color(1),
+            input::CONFIRM => {
+                if let Some(layer) = self.viewport.borrow_mut().layers.get_mut(self.selected) {
+                    layer.visible = !layer.visible;
+                }
+            }
+            input::CANCEL => {
+                if let Some(layer) = self.viewport.borrow_mut().layers.get_mut(self.selected) {
+                    layer.color = None;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        "Layers".to_string()
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode lists the layers of the viewport, from the bottom one to the top one."
+                .to_string(),
+            "Layers can be hidden, reordered and recolored, the changes apply to all the modes."
+                .to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = vec![
+            [
+                input::UP.to_string(),
+                "Selects the previous layer.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Selects the next layer.".to_string(),
+            ],
+            [
+                input::CONFIRM.to_string(),
+                "Hides or shows the selected layer.".to_string(),
+            ],
+            [
+                input::LEFT.to_string(),
+                "Draws the selected layer one step lower.".to_string(),
+            ],
+            [
+                input::RIGHT.to_string(),
+                "Draws the selected layer one step higher.".to_string(),
+            ],
+            [
+                input::ROTATE_LEFT.to_string(),
+                "Gives the previous color to the selected layer.".to_string(),
+            ],
+            [
+                input::ROTATE_RIGHT.to_string(),
+                "Gives the next color to the selected layer.".to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Restores the colors of the selected layer.".to_string(),
+            ],
+        ];
+        keymap.extend(self.viewport.borrow().get_keymap());
+        keymap
+    }
+}
+
+impl UseViewport for Layers {
+    fn draw_in_viewport(&self, ctx: &mut Context) {
+        self.viewport.borrow().draw_in_viewport(ctx);
+    }
+
+    fn x_bounds(&self) -> [f64; 2] {
+        self.viewport.borrow().x_bounds()
+    }
+
+    fn y_bounds(&self) -> [f64; 2] {
+        self.viewport.borrow().y_bounds()
+    }
+
+    fn info(&self) -> String {
+        let viewport = self.viewport.borrow();
+        let selected = match viewport.layers.get(self.selected) {
+            Some(layer) => layer.name.clone(),
+            None => "None".to_string(),
+        };
+        format!("Selected layer: {}{}", selected, viewport.info())
+    }
+
+    fn overlay(&self) -> Option<List<'static>> {
+        let items: Vec<ListItem> = self
+            .viewport
+            .borrow()
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(i, layer)| {
+                let mut style = if layer.visible {
+                    Style::default().fg(Color::White)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                if i == self.selected {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+                let color = match layer.color {
+                    Some(color) => Span::styled(" ■", Style::default().fg(color)),
+                    None => Span::raw(""),
+                };
+                ListItem::new(Spans::from(vec![
+                    Span::styled(
+                        format!(
+                            "{}[{}] {}",
+                            if i == self.selected { ">> " } else { "   " },
+                            if layer.visible { "x" } else { " " },
+                            layer.name
+                        ),
+                        style,
+                    ),
+                    color,
+                ]))
+            })
+            .collect();
+        Some(List::new(items).block(Block::default().title("Layers").borders(Borders::ALL)))
+    }
+}
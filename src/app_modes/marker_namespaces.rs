@@ -0,0 +1,241 @@
+//! Marker namespaces mode allows to hide or solo the namespaces of the markers.
+
+use crate::app_modes::viewport::Viewport;
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::config::TermvizConfig;
+use crate::marker::MarkersListener;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use tui::Frame;
+
+/// Represents the marker namespaces mode.
+pub struct MarkerNamespaces {
+    viewport: Rc<RefCell<Viewport>>,
+    config: Rc<RefCell<TermvizConfig>>,
+    // file the config was loaded from, to which the hidden namespaces are saved
+    config_path: PathBuf,
+    state: ListState,
+    was_saved: bool,
+}
+
+impl MarkerNamespaces {
+    pub fn new(
+        viewport: Rc<RefCell<Viewport>>,
+        config: Rc<RefCell<TermvizConfig>>,
+        config_path: PathBuf,
+    ) -> MarkerNamespaces {
+        MarkerNamespaces {
+            viewport: viewport,
+            config: config,
+            config_path: config_path,
+            state: ListState::default(),
+            was_saved: false,
+        }
+    }
+
+    fn selected_namespace(&self) -> Option<(String, bool)> {
+        let namespaces = self.viewport.borrow().listeners.markers.namespaces();
+        self.state
+            .selected()
+            .and_then(|i| namespaces.into_iter().nth(i))
+            .map(|ns| (ns.name, ns.hidden))
+    }
+
+    fn select(&mut self, offset: i64) {
+        let count = self.viewport.borrow().listeners.markers.namespaces().len() as i64;
+        if count == 0 {
+            self.state.select(None);
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => (i as i64 + offset).rem_euclid(count),
+            None => 0,
+        };
+        self.state.select(Some(i as usize));
+    }
+
+    /// Sets the hidden namespaces in the shared config, so that the topic manager saves them.
+    fn update_config(&mut self) {
+        set_hidden_namespaces(
+            &mut self.config.borrow_mut(),
+            &self.viewport.borrow().listeners.markers,
+        );
+        self.was_saved = false;
+    }
+
+    /// Stores the hidden namespaces in the configuration file of their marker topics.
+    ///
+    /// Only the hidden namespaces are written to the stored configuration, so that the unsaved
+    /// changes of the topic manager are not stored with them.
+    fn save(&mut self) {
+        if let Ok(mut stored) = confy::load_path::<TermvizConfig>(&self.config_path) {
+            set_hidden_namespaces(&mut stored, &self.viewport.borrow().listeners.markers);
+            self.was_saved = confy::store_path(&self.config_path, &stored).is_ok();
+        }
+    }
+}
+
+fn set_hidden_namespaces(config: &mut TermvizConfig, markers: &MarkersListener) {
+    for topic_config in config
+        .marker_topics
+        .iter_mut()
+        .chain(config.marker_array_topics.iter_mut())
+    {
+        topic_config.hidden_namespaces = markers.hidden_namespaces(&topic_config.topic);
+    }
+}
+
+impl<B: Backend> BaseMode<B> for MarkerNamespaces {}
+
+impl AppMode for MarkerNamespaces {
+    fn run(&mut self) {}
+
+    fn reset(&mut self) {
+        self.state.select(None);
+        self.select(0);
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        match input.as_str() {
+            input::UP => self.select(-1),
+            input::DOWN => self.select(1),
+            input::CONFIRM => {
+                if let Some((name, hidden)) = self.selected_namespace() {
+                    self.viewport
+                        .borrow()
+                        .listeners
+                        .markers
+                        .set_namespace_hidden(&name, !hidden);
+                    self.update_config();
+                }
+            }
+            input::RIGHT => {
+                if let Some((name, _)) = self.selected_namespace() {
+                    self.viewport
+                        .borrow()
+                        .listeners
+                        .markers
+                        .solo_namespace(&name);
+                    self.update_config();
+                }
+            }
+            input::CANCEL => {
+                self.viewport
+                    .borrow()
+                    .listeners
+                    .markers
+                    .show_all_namespaces();
+                self.update_config();
+            }
+            input::SAVE_CONFIG => self.save(),
+            _ => (),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        "Marker namespaces".to_string()
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode lists the namespaces of the active markers, and allows to hide them or to show only one of them."
+                .to_string(),
+            "The hidden namespaces can be saved in the configuration of their marker topics."
+                .to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [
+                input::UP.to_string(),
+                "Selects the previous namespace.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Selects the next namespace.".to_string(),
+            ],
+            [
+                input::CONFIRM.to_string(),
+                "Hides or shows the selected namespace.".to_string(),
+            ],
+            [
+                input::RIGHT.to_string(),
+                "Shows only the selected namespace.".to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Shows all the namespaces.".to_string(),
+            ],
+            [
+                input::SAVE_CONFIG.to_string(),
+                "Saves the hidden namespaces to the config file.".to_string(),
+            ],
+        ]
+    }
+}
+
+impl<B: Backend> Drawable<B> for MarkerNamespaces {
+    fn draw(&self, f: &mut Frame<B>) {
+        let title_text = vec![Spans::from(Span::styled(
+            "Marker namespaces",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ))];
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .horizontal_margin(20)
+            .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+            .split(f.size());
+        let title = Paragraph::new(title_text)
+            .block(Block::default().borders(Borders::ALL))
+            .style(Style::default().fg(Color::White))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false });
+
+        let items: Vec<ListItem> = self
+            .viewport
+            .borrow()
+            .listeners
+            .markers
+            .namespaces()
+            .iter()
+            .map(|ns| {
+                let style = if ns.hidden {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                ListItem::new(Span::styled(
+                    format!(
+                        "[{}] {} ({} markers) : {}",
+                        if ns.hidden { " " } else { "x" },
+                        ns.name,
+                        ns.count,
+                        ns.topic
+                    ),
+                    style,
+                ))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .block(
+                Block::default()
+                    .title(if self.was_saved {
+                        "Namespaces (saved)"
+                    } else {
+                        "Namespaces"
+                    })
+                    .borders(Borders::ALL),
+            )
+            .highlight_symbol(">> ");
+        f.render_widget(title, areas[0]);
+        f.render_stateful_widget(list, areas[1], &mut self.state.clone());
+    }
+}
@@ -1,8 +1,14 @@
 //! A module that contains all the builing blocks to create app modes, as well as the app modes themselves.
 
+pub mod explore;
 pub mod image_view;
+pub mod layers;
+pub mod marker_namespaces;
+pub mod playback;
 pub mod send_pose;
 pub mod teleoperate;
+pub mod topic_editor;
+pub mod topic_health;
 pub mod topic_managment;
 pub mod viewport;
 
@@ -21,6 +27,7 @@ pub mod input {
     pub const MODE_7: &str = "Switch to mode 7";
     pub const MODE_8: &str = "Switch to mode 8";
     pub const MODE_9: &str = "Switch to mode 9";
+    pub const PLAYBACK_MODE: &str = "Switch to playback mode";
     pub const LEFT: &str = "Left";
     pub const RIGHT: &str = "Right";
     pub const UP: &str = "Up";
@@ -34,6 +41,12 @@ pub mod input {
     pub const INCREMENT_STEP: &str = "Increment step";
     pub const DECREMENT_STEP: &str = "Decrement step";
     pub const SHOW_HELP: &str = "Show help";
+    pub const FOLLOW_FRAME: &str = "Follow next frame";
+    pub const STEP_FORWARD: &str = "Step forward";
+    pub const RECORD: &str = "Start/stop recording";
+    pub const SLICE_UP: &str = "Raise point cloud slice";
+    pub const SLICE_DOWN: &str = "Lower point cloud slice";
+    pub const SAVE_CONFIG: &str = "Save config";
     pub const UNMAPPED: &str = "Any other";
 }
 
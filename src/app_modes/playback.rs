@@ -0,0 +1,156 @@
+//! Playback mode allows to control the playback of a bag file.
+
+use crate::app_modes::viewport::{UseViewport, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode};
+use crate::bag::BagPlayer;
+use crate::config::PlaybackConfig;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::widgets::canvas::Context;
+
+const MIN_SPEED: f64 = 1. / 16.;
+const MAX_SPEED: f64 = 16.;
+
+/// Represents the playback mode.
+pub struct Playback {
+    viewport: Rc<RefCell<Viewport>>,
+    player: BagPlayer,
+    increment: f64,
+    increment_step: f64,
+}
+
+impl Playback {
+    pub fn new(
+        viewport: Rc<RefCell<Viewport>>,
+        player: BagPlayer,
+        config: PlaybackConfig,
+    ) -> Playback {
+        Playback {
+            viewport: viewport,
+            player: player,
+            increment: config.default_increment,
+            increment_step: config.increment_step,
+        }
+    }
+}
+
+impl<B: Backend> BaseMode<B> for Playback {}
+
+impl AppMode for Playback {
+    fn run(&mut self) {}
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        self.viewport.borrow_mut().handle_input(input);
+        match input.as_str() {
+            input::CONFIRM => self.player.toggle_pause(),
+            input::STEP_FORWARD => self.player.step(),
+            input::CANCEL => self.player.seek_to_start(),
+            input::RIGHT => self.player.seek(self.increment),
+            input::LEFT => self.player.seek(-self.increment),
+            input::UP => self
+                .player
+                .set_speed(MAX_SPEED.min(self.player.speed() * 2.)),
+            input::DOWN => self
+                .player
+                .set_speed(MIN_SPEED.max(self.player.speed() / 2.)),
+            input::INCREMENT_STEP => self.increment += self.increment_step,
+            input::DECREMENT_STEP => {
+                self.increment = self
+                    .increment_step
+                    .max(self.increment - self.increment_step)
+            }
+            _ => (),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        "Playback".to_string()
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode allows to control the playback of the bag file given with --bag."
+                .to_string(),
+            "The viewport is centered on the robot.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = vec![
+            [
+                input::CONFIRM.to_string(),
+                "Pauses or resumes the playback.".to_string(),
+            ],
+            [
+                input::STEP_FORWARD.to_string(),
+                "Pauses the playback and publishes the next message.".to_string(),
+            ],
+            [
+                input::RIGHT.to_string(),
+                "Seeks forward by the seek step.".to_string(),
+            ],
+            [
+                input::LEFT.to_string(),
+                "Seeks backward by the seek step.".to_string(),
+            ],
+            [
+                input::UP.to_string(),
+                "Doubles the playback speed.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Halves the playback speed.".to_string(),
+            ],
+            [
+                input::INCREMENT_STEP.to_string(),
+                "Increases the seek step.".to_string(),
+            ],
+            [
+                input::DECREMENT_STEP.to_string(),
+                "Decreases the seek step.".to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Goes back to the beginning of the bag.".to_string(),
+            ],
+        ];
+        keymap.extend(self.viewport.borrow().get_keymap());
+        keymap
+    }
+}
+
+impl UseViewport for Playback {
+    fn draw_in_viewport(&self, ctx: &mut Context) {
+        self.viewport.borrow().draw_in_viewport(ctx);
+    }
+
+    fn x_bounds(&self) -> [f64; 2] {
+        self.viewport.borrow().x_bounds()
+    }
+
+    fn y_bounds(&self) -> [f64; 2] {
+        self.viewport.borrow().y_bounds()
+    }
+
+    fn info(&self) -> String {
+        let status = if self.player.is_playing() {
+            "Playing"
+        } else if self.player.is_finished() {
+            "Finished"
+        } else {
+            "Paused"
+        };
+        format!(
+            "{} at {}x, Time: {:.1}/{:.1}s, Seek step: {:.1}s{}",
+            status,
+            self.player.speed(),
+            self.player.current_time(),
+            self.player.duration(),
+            self.increment,
+            self.viewport.borrow().info()
+        )
+    }
+}
@@ -4,6 +4,7 @@ use crate::app_modes::viewport::{UseViewport, Viewport};
 use crate::app_modes::{input, AppMode, BaseMode};
 use crate::footprint::get_current_footprint;
 use crate::transformation;
+use crate::transport::Publisher;
 use approx::AbsDiffEq;
 use nalgebra::{Isometry2, Vector2};
 use std::cell::RefCell;
@@ -19,7 +20,7 @@ pub struct SendPose {
     topic: String,
     robot_pose: Isometry2<f64>,
     new_pose: Isometry2<f64>,
-    publisher: rosrust::Publisher<rosrust_msg::geometry_msgs::PoseWithCovarianceStamped>,
+    publisher: Publisher<rosrust_msg::geometry_msgs::PoseWithCovarianceStamped>,
     ghost_active: bool,
 }
 
@@ -27,7 +28,7 @@ impl SendPose {
     pub fn new(topic: &String, viewport: Rc<RefCell<Viewport>>) -> SendPose {
         let base_link_pose = viewport
             .borrow()
-            .tf_listener
+            .transport
             .lookup_transform(
                 &viewport.borrow().static_frame,
                 &viewport.borrow().robot_frame,
@@ -36,9 +37,10 @@ impl SendPose {
             .unwrap()
             .transform;
         let robot_pose = transformation::ros_to_iso2d(&base_link_pose);
+        let publisher = viewport.borrow().transport.advertise(topic, 1).unwrap();
         SendPose {
             viewport: viewport,
-            publisher: rosrust::publish(topic, 1).unwrap(),
+            publisher: publisher,
             increment: 0.1,
             topic: topic.clone(),
             robot_pose: robot_pose.clone(),
@@ -81,7 +83,7 @@ impl AppMode for SendPose {
         let base_link_pose = self
             .viewport
             .borrow()
-            .tf_listener
+            .transport
             .lookup_transform(
                 &self.viewport.borrow().static_frame,
                 &self.viewport.borrow().robot_frame,
@@ -224,8 +226,10 @@ impl UseViewport for SendPose {
 
     fn info(&self) -> String {
         format!(
-            "Topic: /{}, Cursor step: {:.2}",
-            &self.topic, &self.increment
+            "Topic: /{}, Cursor step: {:.2}{}",
+            &self.topic,
+            &self.increment,
+            self.viewport.borrow().info()
         )
     }
 }
@@ -1,7 +1,7 @@
 use crate::app_modes::viewport::{UseViewport, Viewport};
 use crate::app_modes::{input, AppMode, BaseMode};
 use crate::config::TeleopConfig;
-use rosrust;
+use crate::transport::Publisher;
 use rosrust_msg;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -11,7 +11,7 @@ use tui::widgets::canvas::Context;
 pub struct Teleoperate {
     viewport: Rc<RefCell<Viewport>>,
     current_velocities: Velocities,
-    cmd_vel_pub: rosrust::Publisher<rosrust_msg::geometry_msgs::Twist>,
+    cmd_vel_pub: Publisher<rosrust_msg::geometry_msgs::Twist>,
     increment: f64,
     increment_step: f64,
 }
@@ -24,7 +24,11 @@ pub struct Velocities {
 
 impl Teleoperate {
     pub fn new(viewport: Rc<RefCell<Viewport>>, config: TeleopConfig) -> Teleoperate {
-        let cmd_vel_publisher = rosrust::publish(&config.cmd_vel_topic, 1).unwrap();
+        let cmd_vel_publisher = viewport
+            .borrow()
+            .transport
+            .advertise(&config.cmd_vel_topic, 1)
+            .unwrap();
         let initial_velocities = Velocities {
             x: 0.,
             y: 0.,
@@ -146,6 +150,10 @@ impl UseViewport for Teleoperate {
     }
 
     fn info(&self) -> String {
-        format!("Velocity step: {:.2}", &self.increment)
+        format!(
+            "Velocity step: {:.2}{}",
+            &self.increment,
+            self.viewport.borrow().info()
+        )
     }
 }
@@ -0,0 +1,410 @@
+//! Editor of the config of a single topic, used by the topic manager.
+
+use crate::config::{Color, TermvizConfig};
+use crate::pointcloud::{GRADIENTS, STYLES};
+use crate::pose::{valid_style, PATH_STYLES, POSE_ARRAY_STYLES, POSE_STAMPED_STYLES};
+use tui::style::{Color as TuiColor, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::ListItem;
+
+/// Colors of the color picker.
+const PALETTE: [(&str, Color); 10] = [
+    ("red", Color { r: 255, g: 0, b: 0 }),
+    ("green", Color { r: 0, g: 255, b: 0 }),
+    ("blue", Color { r: 0, g: 0, b: 255 }),
+    (
+        "yellow",
+        Color {
+            r: 255,
+            g: 255,
+            b: 0,
+        },
+    ),
+    (
+        "magenta",
+        Color {
+            r: 255,
+            g: 0,
+            b: 255,
+        },
+    ),
+    (
+        "cyan",
+        Color {
+            r: 0,
+            g: 255,
+            b: 255,
+        },
+    ),
+    (
+        "orange",
+        Color {
+            r: 255,
+            g: 128,
+            b: 0,
+        },
+    ),
+    (
+        "purple",
+        Color {
+            r: 128,
+            g: 0,
+            b: 255,
+        },
+    ),
+    (
+        "white",
+        Color {
+            r: 255,
+            g: 255,
+            b: 255,
+        },
+    ),
+    (
+        "gray",
+        Color {
+            r: 128,
+            g: 128,
+            b: 128,
+        },
+    ),
+];
+
+enum Value {
+    Color(Color),
+    Choice(Vec<&'static str>, String),
+    Number {
+        value: f64,
+        step: f64,
+        min: f64,
+        max: f64,
+    },
+    Flag(bool),
+}
+
+struct Field {
+    name: &'static str,
+    value: Value,
+}
+
+impl Field {
+    fn color(name: &'static str, color: &Color) -> Field {
+        Field {
+            name: name,
+            value: Value::Color(color.clone()),
+        }
+    }
+
+    fn choice(name: &'static str, options: Vec<&'static str>, value: &str) -> Field {
+        Field {
+            name: name,
+            value: Value::Choice(options, value.to_string()),
+        }
+    }
+
+    fn number(name: &'static str, value: f64, step: f64, min: f64, max: f64) -> Field {
+        Field {
+            name: name,
+            value: Value::Number {
+                value: value,
+                step: step,
+                min: min,
+                max: max,
+            },
+        }
+    }
+
+    fn flag(name: &'static str, value: bool) -> Field {
+        Field {
+            name: name,
+            value: Value::Flag(value),
+        }
+    }
+
+    /// Changes the value by the given number of steps.
+    fn change(&mut self, steps: i64) {
+        match &mut self.value {
+            Value::Color(color) => {
+                let current = PALETTE.iter().position(|(_, c)| *c == *color);
+                let next = match current {
+                    Some(idx) => (idx as i64 + steps).rem_euclid(PALETTE.len() as i64) as usize,
+                    None => 0,
+                };
+                *color = PALETTE[next].1.clone();
+            }
+            Value::Choice(options, value) => {
+                let next = match options.iter().position(|o| *o == value.as_str()) {
+                    Some(idx) => (idx as i64 + steps).rem_euclid(options.len() as i64) as usize,
+                    None => 0,
+                };
+                *value = options[next].to_string();
+            }
+            Value::Number {
+                value,
+                step,
+                min,
+                max,
+            } => {
+                // rounding avoids accumulating floating point errors
+                let steps_from_min = ((*value - *min) / *step).round() + steps as f64;
+                *value = (*min + steps_from_min * *step).max(*min).min(*max);
+            }
+            Value::Flag(value) => *value = !*value,
+        }
+    }
+
+    /// Returns an error if the value cannot be used, e.g. if it was set by hand in the config file.
+    fn validate(&self) -> Result<(), String> {
+        match &self.value {
+            Value::Choice(options, value) if !options.contains(&value.as_str()) => Err(format!(
+                "Invalid {} \"{}\", expected one of: {}",
+                self.name,
+                value,
+                options.join(", ")
+            )),
+            Value::Number {
+                value, min, max, ..
+            } if value < min || value > max => Err(format!(
+                "Invalid {} {}, expected a value between {} and {}",
+                self.name, value, min, max
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn as_color(&self) -> Color {
+        match &self.value {
+            Value::Color(color) => color.clone(),
+            _ => panic!("{} is not a color", self.name),
+        }
+    }
+
+    fn as_string(&self) -> String {
+        match &self.value {
+            Value::Choice(_, value) => value.clone(),
+            _ => panic!("{} is not a choice", self.name),
+        }
+    }
+
+    fn as_number(&self) -> f64 {
+        match &self.value {
+            Value::Number { value, .. } => *value,
+            _ => panic!("{} is not a number", self.name),
+        }
+    }
+
+    fn as_flag(&self) -> bool {
+        match &self.value {
+            Value::Flag(value) => *value,
+            _ => panic!("{} is not a flag", self.name),
+        }
+    }
+
+    fn to_span(&self) -> Vec<Span<'static>> {
+        match &self.value {
+            Value::Color(color) => {
+                let name = PALETTE
+                    .iter()
+                    .find(|(_, c)| c == color)
+                    .map(|(name, _)| name.to_string())
+                    .unwrap_or_else(|| format!("rgb({}, {}, {})", color.r, color.g, color.b));
+                vec![
+                    Span::styled(
+                        "■ ",
+                        Style::default().fg(TuiColor::Rgb(color.r, color.g, color.b)),
+                    ),
+                    Span::raw(name),
+                ]
+            }
+            Value::Choice(_, value) => vec![Span::raw(value.clone())],
+            Value::Number { value, .. } => vec![Span::raw(format!("{}", value))],
+            Value::Flag(value) => vec![Span::raw(if *value { "yes" } else { "no" })],
+        }
+    }
+}
+
+fn pose_fields(styles: &[&'static str], style: &str, color: &Color, length: f64) -> Vec<Field> {
+    vec![
+        Field::choice("style", styles.to_vec(), valid_style(styles, style)),
+        Field::color("color", color),
+        Field::number("length", length, 0.05, 0.05, 5.),
+    ]
+}
+
+/// Edits the config of a topic, identified by its name and its type.
+pub struct TopicEditor {
+    pub topic: String,
+    pub datatype: String,
+    fields: Vec<Field>,
+    selected: usize,
+}
+
+impl TopicEditor {
+    /// Reads the config of the topic, or returns None if the topic is not configured.
+    pub fn new(config: &TermvizConfig, topic: &str, datatype: &str) -> Option<TopicEditor> {
+        let fields = match datatype {
+            "sensor_msgs/LaserScan" => {
+                let c = config.laser_topics.iter().find(|c| c.topic == topic)?;
+                vec![
+                    Field::color("color", &c.color),
+                    Field::flag("color_by_intensity", c.color_by_intensity),
+                    Field::choice("gradient", GRADIENTS.to_vec(), &c.gradient),
+                    Field::flag("free_space_rays", c.free_space_rays),
+                    Field::number("decay_time", c.decay_time, 0.5, 0., 30.),
+                ]
+            }
+            "nav_msgs/OccupancyGrid" => {
+                let c = config.map_topics.iter().find(|c| c.topic == topic)?;
+                vec![
+                    Field::color("color", &c.color),
+                    Field::number("threshold", c.threshold as f64, 1., 0., 100.),
+                ]
+            }
+            "geometry_msgs/PoseStamped" => {
+                let c = config
+                    .pose_stamped_topics
+                    .iter()
+                    .find(|c| c.topic == topic)?;
+                pose_fields(&POSE_STAMPED_STYLES, &c.style, &c.color, c.length)
+            }
+            "geometry_msgs/PoseArray" => {
+                let c = config.pose_array_topics.iter().find(|c| c.topic == topic)?;
+                pose_fields(&POSE_ARRAY_STYLES, &c.style, &c.color, c.length)
+            }
+            "nav_msgs/Path" => {
+                let c = config.path_topics.iter().find(|c| c.topic == topic)?;
+                pose_fields(&PATH_STYLES, &c.style, &c.color, c.length)
+            }
+            "sensor_msgs/PointCloud2" => {
+                let c = config
+                    .pointcloud2_topics
+                    .iter()
+                    .find(|c| c.topic == topic)?;
+                vec![
+                    Field::choice("style", STYLES.to_vec(), &c.style),
+                    Field::flag("use_rgb", c.use_rgb),
+                    Field::choice("gradient", GRADIENTS.to_vec(), &c.gradient),
+                    Field::flag("categorical", c.categorical),
+                    Field::number("decay_time", c.decay_time, 0.5, 0., 30.),
+                ]
+            }
+            "sensor_msgs/Image" => {
+                let c = config.image_topics.iter().find(|c| c.topic == topic)?;
+                vec![Field::number("rotation", c.rotation as f64, 90., 0., 270.)]
+            }
+            _ => Vec::new(),
+        };
+        Some(TopicEditor {
+            topic: topic.to_string(),
+            datatype: datatype.to_string(),
+            fields: fields,
+            selected: 0,
+        })
+    }
+
+    pub fn select(&mut self, offset: i64) {
+        if !self.fields.is_empty() {
+            self.selected =
+                (self.selected as i64 + offset).rem_euclid(self.fields.len() as i64) as usize;
+        }
+    }
+
+    /// Changes the value of the selected field by the given number of steps.
+    pub fn change(&mut self, steps: i64) {
+        if let Some(field) = self.fields.get_mut(self.selected) {
+            field.change(steps);
+        }
+    }
+
+    /// Returns the first error of the fields, if any.
+    pub fn validate(&self) -> Result<(), String> {
+        self.fields.iter().try_for_each(|f| f.validate())
+    }
+
+    fn field(&self, name: &str) -> &Field {
+        self.fields.iter().find(|f| f.name == name).unwrap()
+    }
+
+    /// Writes the fields to the config of the topic, if they are valid.
+    pub fn write(&self, config: &mut TermvizConfig) -> Result<(), String> {
+        self.validate()?;
+        let topic = &self.topic;
+        match self.datatype.as_str() {
+            "sensor_msgs/LaserScan" => {
+                for c in config.laser_topics.iter_mut().filter(|c| &c.topic == topic) {
+                    c.color = self.field("color").as_color();
+                    c.color_by_intensity = self.field("color_by_intensity").as_flag();
+                    c.gradient = self.field("gradient").as_string();
+                    c.free_space_rays = self.field("free_space_rays").as_flag();
+                    c.decay_time = self.field("decay_time").as_number();
+                }
+            }
+            "nav_msgs/OccupancyGrid" => {
+                for c in config.map_topics.iter_mut().filter(|c| &c.topic == topic) {
+                    c.color = self.field("color").as_color();
+                    c.threshold = self.field("threshold").as_number() as i8;
+                }
+            }
+            "geometry_msgs/PoseStamped" | "geometry_msgs/PoseArray" | "nav_msgs/Path" => {
+                let configs = match self.datatype.as_str() {
+                    "geometry_msgs/PoseStamped" => &mut config.pose_stamped_topics,
+                    "geometry_msgs/PoseArray" => &mut config.pose_array_topics,
+                    _ => &mut config.path_topics,
+                };
+                for c in configs.iter_mut().filter(|c| &c.topic == topic) {
+                    c.style = self.field("style").as_string();
+                    c.color = self.field("color").as_color();
+                    c.length = self.field("length").as_number();
+                }
+            }
+            "sensor_msgs/PointCloud2" => {
+                for c in config
+                    .pointcloud2_topics
+                    .iter_mut()
+                    .filter(|c| &c.topic == topic)
+                {
+                    c.style = self.field("style").as_string();
+                    c.use_rgb = self.field("use_rgb").as_flag();
+                    c.gradient = self.field("gradient").as_string();
+                    c.categorical = self.field("categorical").as_flag();
+                    c.decay_time = self.field("decay_time").as_number();
+                }
+            }
+            "sensor_msgs/Image" => {
+                for c in config.image_topics.iter_mut().filter(|c| &c.topic == topic) {
+                    c.rotation = self.field("rotation").as_number() as i64;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Returns the fields as list items, the selected one being highlighted.
+    pub fn items(&self) -> Vec<ListItem<'static>> {
+        if self.fields.is_empty() {
+            return vec![ListItem::new("No settings for this topic type")];
+        }
+        self.fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let style = if i == self.selected {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                let mut spans = vec![Span::styled(
+                    format!(
+                        "{}{}: ",
+                        if i == self.selected { ">> " } else { "   " },
+                        field.name
+                    ),
+                    style,
+                )];
+                spans.extend(field.to_span());
+                ListItem::new(Spans::from(spans))
+            })
+            .collect()
+    }
+}
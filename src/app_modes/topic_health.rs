@@ -0,0 +1,131 @@
+//! Topic health mode shows the statistics of the messages received by the listeners.
+
+use crate::app_modes::viewport::Viewport;
+use crate::app_modes::{AppMode, BaseMode, Drawable};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph, Row, Table, Wrap};
+use tui::Frame;
+
+/// Represents the topic health mode.
+pub struct TopicHealth {
+    viewport: Rc<RefCell<Viewport>>,
+}
+
+impl TopicHealth {
+    pub fn new(viewport: Rc<RefCell<Viewport>>) -> TopicHealth {
+        TopicHealth { viewport: viewport }
+    }
+}
+
+impl<B: Backend> BaseMode<B> for TopicHealth {}
+
+impl AppMode for TopicHealth {
+    fn run(&mut self) {}
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, _input: &String) {}
+
+    fn get_name(&self) -> String {
+        "Topic health".to_string()
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode lists the topics of the listeners, with their number of received messages, receive rate, latency and time since the last message."
+                .to_string(),
+            "Topics that did not receive any message, or none for longer than the stale timeout, are shown in red."
+                .to_string(),
+            "Topics whose last message could not be read are shown in red, with the error."
+                .to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        Vec::new()
+    }
+}
+
+impl<B: Backend> Drawable<B> for TopicHealth {
+    fn draw(&self, f: &mut Frame<B>) {
+        let title_text = vec![Spans::from(Span::styled(
+            "Topic health",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ))];
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .horizontal_margin(20)
+            .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+            .split(f.size());
+        let title = Paragraph::new(title_text)
+            .block(Block::default().borders(Borders::ALL))
+            .style(Style::default().fg(Color::White))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false });
+
+        let viewport = self.viewport.borrow();
+        let stale_timeout = viewport.health_config.stale_timeout;
+        let rows: Vec<Row> = viewport
+            .listeners
+            .statuses()
+            .into_iter()
+            .map(|status| {
+                let health = &status.health;
+                let error = health.error();
+                let style = if error.is_some() || health.is_stale(stale_timeout) {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default().fg(Color::Green)
+                };
+                Row::new(vec![
+                    status.topic,
+                    status.datatype,
+                    health.count().to_string(),
+                    format!("{:.1} Hz", health.rate()),
+                    match health.latency() {
+                        Some(latency) => format!("{:.0} ms", latency * 1e3),
+                        None => "-".to_string(),
+                    },
+                    match health.last_seen() {
+                        Some(age) => format!("{:.1} s ago", age),
+                        None => "never".to_string(),
+                    },
+                    error.unwrap_or_default(),
+                ])
+                .style(style)
+            })
+            .collect();
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec![
+                    "Topic",
+                    "Type",
+                    "Messages",
+                    "Rate",
+                    "Latency",
+                    "Last message",
+                    "Error",
+                ])
+                .style(Style::default().add_modifier(Modifier::BOLD))
+                .bottom_margin(1),
+            )
+            .block(Block::default().borders(Borders::ALL))
+            .widths(&[
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(8),
+                Constraint::Percentage(10),
+                Constraint::Percentage(10),
+                Constraint::Percentage(12),
+                Constraint::Percentage(20),
+            ])
+            .column_spacing(1);
+        f.render_widget(title, areas[0]);
+        f.render_widget(table, areas[1]);
+    }
+}
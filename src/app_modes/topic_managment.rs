@@ -1,8 +1,13 @@
+use crate::app_modes::topic_editor::TopicEditor;
+use crate::app_modes::viewport::Viewport;
 use crate::app_modes::{input, AppMode, BaseMode, Drawable};
-use crate::config::Color as ConfigColor;
 use crate::config::TermvizConfig;
-use crate::config::{ImageListenerConfig, ListenerConfig, ListenerConfigColor, PoseListenerConfig};
-use rand::Rng;
+use crate::listeners::listener_kinds;
+use crate::transport::Transport;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
 use tui::backend::Backend;
 use tui::layout::{Alignment, Constraint, Direction, Layout};
 use tui::style::{Color, Modifier, Style};
@@ -85,88 +90,50 @@ pub struct TopicManager {
     // Topic Manger loads the active and supported topics into two lists.
     // The User can shift elements between available and selected topics.
     // topics can only be present in on of the lists.
+    // Every shift is applied to the shared config and to the listeners of the viewport.
+    // The config of an active topic can be changed in the editor, with a live preview.
     availible_topics: SelectableTopics,
     selected_topics: SelectableTopics,
-    config: TermvizConfig,
+    config: Rc<RefCell<TermvizConfig>>,
+    // file the config was loaded from, to which it is saved
+    config_path: PathBuf,
+    viewport: Rc<RefCell<Viewport>>,
     selection_mode: bool,
     was_saved: bool,
+    editor: Option<TopicEditor>,
+    // config before editing, restored if the edition is cancelled
+    config_before_edit: Option<TermvizConfig>,
+    editor_error: Option<String>,
 }
 
 impl TopicManager {
-    pub fn new(config: TermvizConfig) -> TopicManager {
-        let config = config.clone();
+    pub fn new(
+        config: Rc<RefCell<TermvizConfig>>,
+        config_path: PathBuf,
+        viewport: Rc<RefCell<Viewport>>,
+        transport: Arc<dyn Transport>,
+    ) -> TopicManager {
+        let kinds = listener_kinds();
 
-        // Get all topics currently active in the config and sort them by topic type
-        let active_laser_topics: Vec<[String; 2]> = config
-            .laser_topics
+        // Get all topics currently active in the config with their topic type
+        let all_active_topics: Vec<[String; 2]> = kinds
             .iter()
-            .map(|i| [i.topic.clone(), "sensor_msgs/LaserScan".to_string()])
-            .collect();
-        let active_marker_array_topics: Vec<[String; 2]> = config
-            .marker_array_topics
-            .iter()
-            .map(|i| {
-                [
-                    i.topic.clone(),
-                    "visualization_msgs/MarkerArray".to_string(),
-                ]
+            .flat_map(|kind| {
+                (kind.topics)(&config.borrow())
+                    .into_iter()
+                    .map(move |topic| [topic, kind.datatype.to_string()])
             })
             .collect();
-        let active_marker_topics: Vec<[String; 2]> = config
-            .marker_topics
-            .iter()
-            .map(|i| [i.topic.clone(), "visualization_msgs/Marker".to_string()])
-            .collect();
-        let active_pose_stamped_topics: Vec<[String; 2]> = config
-            .pose_stamped_topics
-            .iter()
-            .map(|i| [i.topic.clone(), "geometry_msgs/PoseStamped".to_string()])
-            .collect();
-        let active_pose_array_topics: Vec<[String; 2]> = config
-            .pose_array_topics
-            .iter()
-            .map(|i| [i.topic.clone(), "geometry_msgs/PoseArray".to_string()])
-            .collect();
-        let active_path_topics: Vec<[String; 2]> = config
-            .path_topics
-            .iter()
-            .map(|i| [i.topic.clone(), "nav_msgs/Path".to_string()])
-            .collect();
-        let active_image_topics: Vec<[String; 2]> = config
-            .path_topics
-            .iter()
-            .map(|i| [i.topic.clone(), "sensor_msgs/Image".to_string()])
-            .collect();
-        // Collect them into a big list
-        let all_active_topics = [
-            active_image_topics,
-            active_laser_topics,
-            active_marker_array_topics,
-            active_marker_topics,
-            active_path_topics,
-            active_pose_array_topics,
-            active_pose_stamped_topics,
-        ]
-        .concat();
 
-        // We could get this from config, but would need some breaking changes in config
-        let supported_topic_types = vec![
-            "geometry_msgs/PoseArray".to_string(),
-            "geometry_msgs/PoseStamped".to_string(),
-            "nav_msgs/Path".to_string(),
-            "sensor_msgs/Image".to_string(),
-            "sensor_msgs/LaserScan".to_string(),
-            "visualization_msgs/Marker".to_string(),
-            "visualization_msgs/MarkerArray".to_string(),
-        ];
         // Collect all topics, which:
         //  - are supported
         //  - are inactive
-        let supported_topics: Vec<[String; 2]> = rosrust::topics()
+        let supported_topics: Vec<[String; 2]> = transport
+            .topics()
             .unwrap()
             .iter()
             .map(|topic| [topic.name.to_string(), topic.datatype.to_string()])
-            .filter(|el| supported_topic_types.contains(&el[1].to_string()))
+            .filter(|el| kinds.iter().any(|kind| kind.datatype == el[1]))
             .filter(|el| !all_active_topics.contains(&el))
             .collect();
 
@@ -178,8 +145,13 @@ impl TopicManager {
             availible_topics: supported_topic_list,
             selected_topics: SelectableTopics::new(all_active_topics),
             config: config,
+            config_path: config_path,
+            viewport: viewport,
             selection_mode: true,
             was_saved: false,
+            editor: None,
+            config_before_edit: None,
+            editor_error: None,
         }
     }
 
@@ -189,6 +161,7 @@ impl TopicManager {
         }
         let x = self.availible_topics.pop();
         self.selected_topics.add(x);
+        self.apply();
     }
     pub fn shift_active_element_left(&mut self) {
         if self.selected_topics.items.is_empty() {
@@ -196,84 +169,89 @@ impl TopicManager {
         }
         let x = self.selected_topics.pop();
         self.availible_topics.add(x);
+        self.apply();
     }
 
-    pub fn save(&mut self) {
-        let mut config = self.config.clone();
+    /// Updates the shared config to the active topics, and creates and drops the listeners of
+    /// the viewport accordingly.
+    ///
+    /// Topics that stay active keep their config, new ones get a default config.
+    pub fn apply(&mut self) {
+        let mut config = self.config.borrow().clone();
+        for kind in listener_kinds() {
+            let topics: Vec<String> = self
+                .selected_topics
+                .items
+                .iter()
+                .filter(|topic| topic[1] == kind.datatype)
+                .map(|topic| topic[0].clone())
+                .collect();
+            (kind.set_topics)(&mut config, &topics);
+        }
+        *self.config.borrow_mut() = config;
+        self.update_listeners();
+    }
 
-        // Flush all to get a new config
-        config.laser_topics.clear();
-        config.marker_array_topics.clear();
-        config.marker_topics.clear();
-        config.pose_stamped_topics.clear();
-        config.pose_array_topics.clear();
-        config.path_topics.clear();
+    /// Creates, drops and updates the listeners of the viewport to follow the shared config.
+    fn update_listeners(&mut self) {
+        let config = self.config.borrow();
+        let mut viewport = self.viewport.borrow_mut();
+        viewport.listeners.update(
+            config.laser_topics.clone(),
+            config.marker_topics.clone(),
+            config.marker_array_topics.clone(),
+            config.map_topics.clone(),
+            config.pose_stamped_topics.clone(),
+            config.pose_array_topics.clone(),
+            config.pointcloud2_topics.clone(),
+            config.path_topics.clone(),
+        );
+        viewport.update_layers();
+        self.was_saved = false;
+    }
 
-        // Fill the respective topics
-        // The current implementation hardcodes where the topics must go
-        // This could be handled by a more descriptive config structure
-        let mut rng = rand::thread_rng();
-        for topic in self.selected_topics.items.iter() {
-            match topic[1].clone().as_ref() {
-                "sensor_msgs/LaserScan" => config.laser_topics.push(ListenerConfigColor {
-                    topic: topic[0].clone(),
-                    color: ConfigColor {
-                        r: rng.gen_range(0..255),
-                        g: rng.gen_range(0..255),
-                        b: rng.gen_range(0..255),
-                    },
-                }),
-                "visualization_msgs/MarkerArray" => {
-                    config.marker_array_topics.push(ListenerConfig {
-                        topic: topic[0].clone(),
-                    })
-                }
-                "visualization_msgs/Marker" => config.marker_topics.push(ListenerConfig {
-                    topic: topic[0].clone(),
-                }),
-                "geometry_msgs/PoseStamped" => {
-                    config.pose_stamped_topics.push(PoseListenerConfig {
-                        topic: topic[0].clone(),
-                        color: ConfigColor {
-                            r: rng.gen_range(0..255),
-                            g: rng.gen_range(0..255),
-                            b: rng.gen_range(0..255),
-                        },
-                        length: 0.2,
-                        style: "axis".to_string(),
-                    })
-                }
-                "geometry_msgs/PoseArray" => config.pose_array_topics.push(PoseListenerConfig {
-                    topic: topic[0].clone(),
-                    color: ConfigColor {
-                        r: rng.gen_range(0..255),
-                        g: rng.gen_range(0..255),
-                        b: rng.gen_range(0..255),
-                    },
-                    length: 0.2,
-                    style: "axis".to_string(),
-                }),
-                "nav_msgs/Path" => config.path_topics.push(PoseListenerConfig {
-                    topic: topic[0].clone(),
-                    color: ConfigColor {
-                        r: rng.gen_range(0..255),
-                        g: rng.gen_range(0..255),
-                        b: rng.gen_range(0..255),
-                    },
-                    length: 0.2,
-                    style: "axis".to_string(),
-                }),
-                "sensor_msg/Image" => config.image_topics.push(ImageListenerConfig {
-                    topic: topic[0].clone(),
-                    rotation: 0,
-                }),
+    /// Opens the editor for the selected active topic.
+    fn open_editor(&mut self) {
+        let i = match self.selected_topics.state.selected() {
+            Some(i) if i < self.selected_topics.items.len() => i,
+            _ => return,
+        };
+        let [topic, datatype] = self.selected_topics.items[i].clone();
+        let config = self.config.borrow().clone();
+        self.editor = TopicEditor::new(&config, &topic, &datatype);
+        self.editor_error = self.editor.as_ref().and_then(|e| e.validate().err());
+        self.config_before_edit = Some(config);
+    }
 
-                _ => (),
+    /// Closes the editor, restoring the config from before the edition if cancelled.
+    fn close_editor(&mut self, cancel: bool) {
+        self.editor = None;
+        self.editor_error = None;
+        if let Some(config) = self.config_before_edit.take() {
+            if cancel {
+                *self.config.borrow_mut() = config;
+                self.update_listeners();
             }
         }
+    }
+
+    /// Changes the selected field of the editor, and applies it if it is valid.
+    fn change_field(&mut self, steps: i64) {
+        let editor = match self.editor.as_mut() {
+            Some(editor) => editor,
+            None => return,
+        };
+        editor.change(steps);
+        let res = editor.write(&mut self.config.borrow_mut());
+        self.editor_error = res.err();
+        if self.editor_error.is_none() {
+            self.update_listeners();
+        }
+    }
 
-        // Store and exit termviz
-        let _ = confy::store("termviz", "termviz", &(config));
+    /// Stores the shared config, so that the active topics are used at the next start.
+    pub fn save(&mut self) {
+        let _ = confy::store_path(&self.config_path, &*self.config.borrow());
         self.was_saved = true
     }
 }
@@ -284,11 +262,26 @@ impl AppMode for TopicManager {
     fn run(&mut self) {}
     fn reset(&mut self) {}
     fn get_description(&self) -> Vec<String> {
-        vec!["Topic manager can enable and disable displayed topics".to_string()]
+        vec![
+            "Topic manager can enable and disable displayed topics".to_string(),
+            "Changes are applied immediately, and can be saved to the config file.".to_string(),
+            "The settings of an active topic, e.g. its color, can be changed in the editor."
+                .to_string(),
+        ]
     }
 
     fn handle_input(&mut self, input: &String) {
-        if self.selection_mode {
+        if let Some(editor) = self.editor.as_mut() {
+            match input.as_str() {
+                input::UP => editor.select(-1),
+                input::DOWN => editor.select(1),
+                input::LEFT => self.change_field(-1),
+                input::RIGHT => self.change_field(1),
+                input::CONFIRM => self.close_editor(false),
+                input::CANCEL => self.close_editor(true),
+                _ => (),
+            }
+        } else if self.selection_mode {
             match input.as_str() {
                 input::UP => self.availible_topics.previous(),
                 input::DOWN => self.availible_topics.next(),
@@ -306,6 +299,7 @@ impl AppMode for TopicManager {
                 input::UP => self.selected_topics.previous(),
                 input::DOWN => self.selected_topics.next(),
                 input::LEFT => self.shift_active_element_left(),
+                input::RIGHT => self.open_editor(),
                 input::ROTATE_LEFT => {
                     self.selection_mode = true;
                     self.availible_topics.state.select(Some(0));
@@ -329,11 +323,15 @@ impl AppMode for TopicManager {
             ],
             [
                 input::RIGHT.to_string(),
-                "Shifts an element to the right if the supported topic list is active".to_string(),
+                "Shifts an element to the right if the supported topic list is active, opens the settings editor of the selected active topic, or increases the edited value".to_string(),
             ],
             [
                 input::LEFT.to_string(),
-                "Shifts an element to the left if the active list is active".to_string(),
+                "Shifts an element to the left if the active list is active, or decreases the edited value".to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Closes the settings editor and reverts its changes".to_string(),
             ],
             [
                 input::ROTATE_RIGHT.to_string(),
@@ -344,7 +342,11 @@ impl AppMode for TopicManager {
                 "Changes the list where items are selected to the supported topics list"
                     .to_string(),
             ],
-            [input::CONFIRM.to_string(), "Saves to config".to_string()],
+            [
+                input::CONFIRM.to_string(),
+                "Saves the active topics to the config file, or closes the settings editor"
+                    .to_string(),
+            ],
         ]
     }
 
@@ -377,66 +379,88 @@ impl<B: Backend> Drawable<B> for TopicManager {
             .alignment(Alignment::Center)
             .wrap(Wrap { trim: false });
 
-        if !self.was_saved {
-            let left_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .margin(1)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-                .split(areas[2]);
-            // Widget creation
-            let items: Vec<ListItem> = self
-                .availible_topics
-                .items
-                .iter()
-                .map(|i| ListItem::new(format!("{} : {}", i[0], i[1])))
-                .collect();
-            // The `List` widget is then built with those items.
-            let list = List::new(items)
-                .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-                .block(
-                    Block::default()
-                        .title("Available Topics")
-                        .borders(Borders::ALL),
-                )
-                .highlight_symbol(">> ");
+        let constraints = if self.editor.is_some() {
+            vec![
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+                Constraint::Percentage(34),
+            ]
+        } else {
+            vec![Constraint::Percentage(50), Constraint::Percentage(50)]
+        };
+        let left_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .margin(1)
+            .constraints(constraints)
+            .split(areas[2]);
+        // Widget creation
+        let items: Vec<ListItem> = self
+            .availible_topics
+            .items
+            .iter()
+            .map(|i| ListItem::new(format!("{} : {}", i[0], i[1])))
+            .collect();
+        // The `List` widget is then built with those items.
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .block(
+                Block::default()
+                    .title("Available Topics")
+                    .borders(Borders::ALL),
+            )
+            .highlight_symbol(">> ");
 
-            let selected_items: Vec<ListItem> = self
-                .selected_topics
-                .items
-                .iter()
-                .map(|i| ListItem::new(i[0].as_ref()))
-                .collect();
-            // The `List` widget is then built with those items.
-            let selected_list = List::new(selected_items)
-                .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-                .block(
-                    Block::default()
-                        .title("Active Topics")
-                        .borders(Borders::ALL),
-                )
-                .highlight_symbol(">> ");
-            // Finally the widget is rendered using the associated state. `events.state` is
-            // effectively the only thing that we will "remember" from this draw call.
-            f.render_widget(title, areas[0]);
-            f.render_stateful_widget(
-                list,
-                left_chunks[0],
-                &mut self.availible_topics.state.clone(),
-            );
-            f.render_stateful_widget(
-                selected_list,
-                left_chunks[1],
-                &mut self.selected_topics.state.clone(),
+        let selected_items: Vec<ListItem> = self
+            .selected_topics
+            .items
+            .iter()
+            .map(|i| ListItem::new(i[0].as_ref()))
+            .collect();
+        // The `List` widget is then built with those items.
+        let selected_list = List::new(selected_items)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .block(
+                Block::default()
+                    .title("Active Topics")
+                    .borders(Borders::ALL),
+            )
+            .highlight_symbol(">> ");
+        // Finally the widget is rendered using the associated state. `events.state` is
+        // effectively the only thing that we will "remember" from this draw call.
+        f.render_widget(title, areas[0]);
+        f.render_stateful_widget(
+            list,
+            left_chunks[0],
+            &mut self.availible_topics.state.clone(),
+        );
+        f.render_stateful_widget(
+            selected_list,
+            left_chunks[1],
+            &mut self.selected_topics.state.clone(),
+        );
+        if let Some(editor) = &self.editor {
+            let editor_list = List::new(editor.items()).block(
+                Block::default()
+                    .title(format!("Settings of {}", editor.topic))
+                    .borders(Borders::ALL),
             );
-        } else {
-            let user_info = Paragraph::new(Spans::from(Span::raw(
-                "Config has been saved, restart termviz to use it. \n Switch to any other mode to continue"
-            )))
+            f.render_widget(editor_list, left_chunks[2]);
+        }
+        let status = match (&self.editor_error, &self.editor) {
+            (Some(error), _) => Span::styled(error.clone(), Style::default().fg(Color::Red)),
+            (None, Some(_)) => Span::raw(
+                "Changes are previewed immediately, confirm to keep them or cancel to revert them.",
+            ),
+            (None, None) if self.was_saved => Span::raw("Config has been saved."),
+            (None, None) => Span::raw(
+                "Changes are applied immediately, confirm to save them to the config file.",
+            ),
+        };
+        let user_info = Paragraph::new(Spans::from(status))
             .block(Block::default().borders(Borders::NONE))
             .style(Style::default().fg(Color::White))
             .alignment(Alignment::Center)
             .wrap(Wrap { trim: false });
-            f.render_widget(user_info, areas[1]);
-        }
+        f.render_widget(user_info, areas[1]);
     }
 }
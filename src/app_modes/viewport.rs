@@ -2,16 +2,25 @@
 //! A mode can borrow the viewport to draw whatever is needed.
 
 use crate::app_modes::{input, AppMode, Drawable};
+use crate::config::{HealthConfig, RecordConfig};
 use crate::footprint::get_current_footprint;
+use crate::health::TopicHealth;
 use crate::listeners::Listeners;
+use crate::pointcloud::{self, ColoredPoint, Region};
+use crate::record::BagRecorder;
+use crate::trail::Trail;
 use crate::transformation;
+use crate::transport::Transport;
+use nalgebra::geometry::Point3;
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use tui::backend::Backend;
-use tui::layout::{Constraint, Layout};
+use tui::layout::{Constraint, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
 use tui::text::{Span, Spans};
 use tui::widgets::canvas::{Canvas, Context, Line, Points};
-use tui::widgets::{Block, Borders};
+use tui::widgets::{Block, Borders, Clear, List};
 use tui::Frame;
 
 /// Represents modes that use the viewport.
@@ -32,6 +41,11 @@ pub trait UseViewport: AppMode {
 
     /// Returns additional information that will be displayed on the top bar of the viewport.
     fn info(&self) -> String;
+
+    /// Returns a panel to draw over the right side of the viewport, if any.
+    fn overlay(&self) -> Option<List<'static>> {
+        None
+    }
 }
 
 impl<B: Backend, T: UseViewport> Drawable<B> for T {
@@ -59,13 +73,195 @@ impl<B: Backend, T: UseViewport> Drawable<B> for T {
                 self.draw_in_viewport(ctx);
             });
         f.render_widget(canvas, chunks[0]);
+
+        if let Some(overlay) = self.overlay() {
+            let width = chunks[0].width.min(50);
+            let area = Rect::new(
+                chunks[0].x + chunks[0].width - width,
+                chunks[0].y + 1,
+                width,
+                chunks[0].height.saturating_sub(1),
+            );
+            f.render_widget(Clear, area);
+            f.render_widget(overlay, area);
+        }
+    }
+}
+
+/// Listener drawn by a layer of the viewport, with its index in `Listeners`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LayerKind {
+    Map(usize),
+    PointCloud(usize),
+    Markers,
+    Laser(usize),
+    Robot,
+    PoseStamped(usize),
+    Path(usize),
+    PoseArray(usize),
+}
+
+/// A layer of the viewport, drawn on top of the previous ones.
+pub struct Layer {
+    pub name: String,
+    pub kind: LayerKind,
+    pub visible: bool,
+    /// Color replacing the colors of the listener, if any.
+    pub color: Option<Color>,
+}
+
+impl Layer {
+    fn new(name: String, kind: LayerKind) -> Layer {
+        Layer {
+            name: name,
+            kind: kind,
+            visible: true,
+            color: None,
+        }
+    }
+
+    fn recolor(&self, color: Color) -> Color {
+        self.color.unwrap_or(color)
+    }
+}
+
+/// Brightness of the stale data, when it is dimmed.
+const STALE_BRIGHTNESS: f64 = 0.3;
+
+/// Brightness of the data of the previous messages at the end of their decay time.
+const DECAYED_BRIGHTNESS: f64 = 0.2;
+
+/// Number of colors of the legend of the height maps.
+const LEGEND_WIDTH: usize = 12;
+
+/// Step by which the slices of the point clouds are raised or lowered, in meters.
+const SLICE_STEP: f64 = 0.1;
+
+/// Brightness of the free space rays of the lasers.
+const FREE_SPACE_BRIGHTNESS: f64 = 0.3;
+
+/// Returns the color darkened to the brightness, between 0 and 1.
+fn fade(color: Color, brightness: f64) -> Color {
+    let scale = |c: u8| (c as f64 * brightness) as u8;
+    match color {
+        Color::Rgb(r, g, b) => Color::Rgb(scale(r), scale(g), scale(b)),
+        _ if brightness < 0.5 => Color::DarkGray,
+        _ => color,
+    }
+}
+
+/// Returns the brightness of the data of a previous message, given its age as a fraction of the
+/// decay time.
+fn decay_brightness(age: f64) -> f64 {
+    1. - age * (1. - DECAYED_BRIGHTNESS)
+}
+
+/// Draws the points to keep with the given brightness, with one draw call per color.
+fn draw_colored_points(
+    ctx: &mut Context,
+    layer: &Layer,
+    points: &[ColoredPoint],
+    keep: &dyn Fn(&ColoredPoint) -> bool,
+    brightness: f64,
+) {
+    let mut batches: Vec<(Color, Vec<(f64, f64)>)> = Vec::new();
+    // tui colors are not hashable, the few colors other than RGB ones are searched instead
+    let mut rgb_batches: HashMap<(u8, u8, u8), usize> = HashMap::new();
+    for pt in points.iter().filter(|pt| keep(pt)) {
+        let color = fade(layer.recolor(pt.color), brightness);
+        let batch = match color {
+            Color::Rgb(r, g, b) => *rgb_batches.entry((r, g, b)).or_insert_with(|| {
+                batches.push((color, Vec::new()));
+                batches.len() - 1
+            }),
+            _ => match batches.iter().position(|(c, _)| *c == color) {
+                Some(batch) => batch,
+                None => {
+                    batches.push((color, Vec::new()));
+                    batches.len() - 1
+                }
+            },
+        };
+        batches[batch].1.push((pt.point.x, pt.point.y));
+    }
+    for (color, coords) in batches {
+        ctx.draw(&Points {
+            coords: &coords,
+            color: color,
+        });
+    }
+}
+
+/// Draws the points of the previous messages, fading with their age, then the points of the
+/// last message.
+fn draw_with_trail(
+    ctx: &mut Context,
+    layer: &Layer,
+    trail: &Trail<Vec<ColoredPoint>>,
+    points: &[ColoredPoint],
+    keep: &dyn Fn(&ColoredPoint) -> bool,
+    brightness: f64,
+) {
+    for (age, previous) in trail.previous() {
+        draw_colored_points(
+            ctx,
+            layer,
+            previous,
+            keep,
+            brightness * decay_brightness(age),
+        );
     }
+    draw_colored_points(ctx, layer, points, keep, brightness);
+}
+
+/// Returns the layers of the listeners, in their default drawing order.
+fn default_layers(listeners: &Listeners) -> Vec<Layer> {
+    let mut layers = Vec::new();
+    for (i, map) in listeners.maps.iter().enumerate() {
+        layers.push(Layer::new(
+            format!("Map: {}", map.config.topic),
+            LayerKind::Map(i),
+        ));
+    }
+    for (i, pointcloud) in listeners.pointclouds.iter().enumerate() {
+        layers.push(Layer::new(
+            format!("Point cloud: {}", pointcloud.config.topic),
+            LayerKind::PointCloud(i),
+        ));
+    }
+    layers.push(Layer::new("Markers".to_string(), LayerKind::Markers));
+    for (i, laser) in listeners.lasers.iter().enumerate() {
+        layers.push(Layer::new(
+            format!("Laser: {}", laser.config.topic),
+            LayerKind::Laser(i),
+        ));
+    }
+    layers.push(Layer::new("Robot".to_string(), LayerKind::Robot));
+    for (i, pose_stamped) in listeners.pose_stamped.iter().enumerate() {
+        layers.push(Layer::new(
+            format!("Pose: {}", pose_stamped.config.topic),
+            LayerKind::PoseStamped(i),
+        ));
+    }
+    for (i, path) in listeners.paths.iter().enumerate() {
+        layers.push(Layer::new(
+            format!("Path: {}", path.config.topic),
+            LayerKind::Path(i),
+        ));
+    }
+    for (i, pose_array) in listeners.pose_array.iter().enumerate() {
+        layers.push(Layer::new(
+            format!("Pose array: {}", pose_array.config.topic),
+            LayerKind::PoseArray(i),
+        ));
+    }
+    layers
 }
 
 pub struct Viewport {
     pub static_frame: String,
     pub robot_frame: String,
-    pub tf_listener: Arc<rustros_tf::TfListener>,
+    pub transport: Arc<dyn Transport>,
     pub initial_bounds: Vec<f64>,
     pub footprint: Vec<(f64, f64)>,
     pub axis_length: f64,
@@ -73,33 +269,403 @@ pub struct Viewport {
     pub zoom_factor: f64,
     pub terminal_size: (u16, u16),
     pub listeners: Listeners, // TODO split properly config and listeners
+    pub layers: Vec<Layer>,
+    pub record_config: RecordConfig,
+    pub recorder: Option<BagRecorder>,
+    pub record_error: Option<String>,
+    pub health_config: HealthConfig,
+    pub slice_offset: f64,
 }
 
 impl Viewport {
     pub fn new(
         static_frame: &String,
         robot_frame: &String,
-        tf_listener: Arc<rustros_tf::TfListener>,
+        transport: Arc<dyn Transport>,
         initial_bounds: &Vec<f64>,
         footprint: &Vec<(f64, f64)>,
         axis_length: f64,
         zoom_factor: f64,
         listeners: Listeners,
         terminal_size: (u16, u16),
+        record_config: &RecordConfig,
+        health_config: &HealthConfig,
     ) -> Viewport {
-        Viewport {
+        let viewport = Viewport {
             static_frame: static_frame.clone(),
             robot_frame: robot_frame.clone(),
-            tf_listener: tf_listener.clone(),
+            transport: transport.clone(),
             initial_bounds: initial_bounds.clone(),
             zoom: 1.0,
             zoom_factor: zoom_factor,
             footprint: footprint.clone(),
             axis_length: axis_length,
+            layers: default_layers(&listeners),
             listeners: listeners,
             terminal_size: terminal_size,
+            record_config: record_config.clone(),
+            recorder: None,
+            record_error: None,
+            health_config: health_config.clone(),
+            slice_offset: 0.,
+        };
+        viewport.update_pointcloud_views();
+        viewport
+    }
+
+    /// Returns the size of a dot of the viewport, with two braille dots per column of the
+    /// terminal.
+    fn dot_size(&self) -> f64 {
+        let scale_factor = self.terminal_size.0 as f64 / self.terminal_size.1 as f64 * 0.5;
+        (self.initial_bounds[1] - self.initial_bounds[0]) / self.zoom * scale_factor
+            / (self.terminal_size.0 as f64 * 2.)
+    }
+
+    /// Passes the dot size and the slice offset to the point cloud listeners, which keep only the
+    /// points that are drawn.
+    fn update_pointcloud_views(&self) {
+        let dot_size = self.dot_size();
+        for pointcloud in &self.listeners.pointclouds {
+            pointcloud.set_view(dot_size, self.slice_offset);
         }
     }
+
+    /// Updates the layers to the current listeners.
+    ///
+    /// The layers that still exist keep their order, visibility and color, and the new ones
+    /// are inserted at their default position.
+    pub fn update_layers(&mut self) {
+        let defaults = default_layers(&self.listeners);
+        let mut layers: Vec<Layer> = self
+            .layers
+            .drain(..)
+            .filter_map(|mut layer| {
+                let kind = defaults.iter().find(|l| l.name == layer.name)?.kind;
+                layer.kind = kind;
+                Some(layer)
+            })
+            .collect();
+        for (i, layer) in defaults.into_iter().enumerate() {
+            if !layers.iter().any(|l| l.name == layer.name) {
+                layers.insert(i.min(layers.len()), layer);
+            }
+        }
+        self.layers = layers;
+        // the new point cloud listeners need the view too
+        self.update_pointcloud_views();
+    }
+
+    /// Returns the topics of the visible layers that did not receive data recently.
+    pub fn stale_topics(&self) -> Vec<String> {
+        let listeners = &self.listeners;
+        let mut topics = Vec::new();
+        for layer in self.layers.iter().filter(|l| l.visible) {
+            let healths = match layer.kind {
+                LayerKind::Map(i) => vec![(
+                    &listeners.maps[i].config.topic,
+                    listeners.maps[i].health.clone(),
+                )],
+                LayerKind::PointCloud(i) => vec![(
+                    &listeners.pointclouds[i].config.topic,
+                    listeners.pointclouds[i].health.clone(),
+                )],
+                LayerKind::Laser(i) => vec![(
+                    &listeners.lasers[i].config.topic,
+                    listeners.lasers[i].health.clone(),
+                )],
+                LayerKind::PoseStamped(i) => vec![(
+                    &listeners.pose_stamped[i].config.topic,
+                    listeners.pose_stamped[i].health.clone(),
+                )],
+                LayerKind::Path(i) => vec![(
+                    &listeners.paths[i].config.topic,
+                    listeners.paths[i].health.clone(),
+                )],
+                LayerKind::PoseArray(i) => vec![(
+                    &listeners.pose_array[i].config.topic,
+                    listeners.pose_array[i].health.clone(),
+                )],
+                LayerKind::Markers | LayerKind::Robot => Vec::new(),
+            };
+            for (topic, health) in healths {
+                if health.is_stale(self.health_config.stale_timeout) {
+                    topics.push(topic.clone());
+                }
+            }
+            if layer.kind == LayerKind::Markers {
+                for (topic, health) in listeners.markers.healths() {
+                    if health.is_stale(self.health_config.stale_timeout) {
+                        topics.push(topic);
+                    }
+                }
+            }
+        }
+        topics
+    }
+
+    /// Starts recording the topics of the listeners to a new bag file, or stops the recording.
+    pub fn toggle_recording(&mut self) {
+        self.record_error = match self.recorder.take() {
+            Some(recorder) => recorder.stop().err(),
+            None => {
+                let file_name = format!(
+                    "termviz_{}.bag",
+                    chrono::Local::now().format("%Y-%m-%d-%H-%M-%S")
+                );
+                let path = Path::new(&self.record_config.directory).join(file_name);
+                match BagRecorder::start(&path, &self.transport, self.listeners.topics()) {
+                    Ok(recorder) => {
+                        self.recorder = Some(recorder);
+                        None
+                    }
+                    Err(e) => Some(e),
+                }
+            }
+        };
+    }
+
+    /// Returns true if a point cloud is sliced by height, so that the slice offset applies.
+    fn has_slices(&self) -> bool {
+        self.listeners
+            .pointclouds
+            .iter()
+            .any(|p| p.config.min_z.is_some() || p.config.max_z.is_some())
+    }
+
+    /// Returns the recording status.
+    fn record_info(&self) -> String {
+        if let Some(error) = &self.record_error {
+            return format!(", Recording failed: {}", error);
+        }
+        match &self.recorder {
+            Some(recorder) => match recorder.error() {
+                Some(error) => format!(", Recording failed: {}", error),
+                None => format!(
+                    ", REC {} ({:.1} MB)",
+                    recorder.path().display(),
+                    recorder.size() as f64 / 1e6
+                ),
+            },
+            None => "".to_string(),
+        }
+    }
+
+    /// Draws the visible layers, for a view with the given bounds.
+    pub fn draw_in_bounds(&self, ctx: &mut Context, x_bounds: [f64; 2], y_bounds: [f64; 2]) {
+        for layer in self.layers.iter().filter(|l| l.visible) {
+            self.draw_layer(ctx, layer, x_bounds, y_bounds);
+            ctx.layer();
+        }
+    }
+
+    /// Returns the brightness of the data of a listener, or None if it is not drawn. The data is
+    /// stale if it is older than the timeout of the listener, and is then drawn according to the
+    /// stale data setting of the listener, or to the global one if it has none.
+    fn data_brightness(
+        &self,
+        timeout: Option<f64>,
+        stale_data: &Option<String>,
+        health: &TopicHealth,
+    ) -> Option<f64> {
+        match timeout {
+            Some(timeout) if health.is_stale(timeout) => {
+                let stale_data = stale_data
+                    .as_ref()
+                    .unwrap_or(&self.health_config.stale_data);
+                match stale_data.as_str() {
+                    "hide" => None,
+                    _ => Some(STALE_BRIGHTNESS),
+                }
+            }
+            _ => Some(1.),
+        }
+    }
+
+    /// Draws the height map cells of a point cloud, with the legend of their colors in the
+    /// bottom left corner of the viewport.
+    fn draw_height_map(
+        &self,
+        ctx: &mut Context,
+        layer: &Layer,
+        i: usize,
+        brightness: f64,
+        x_bounds: [f64; 2],
+        y_bounds: [f64; 2],
+    ) {
+        let cloud = &self.listeners.pointclouds[i];
+        let config = &cloud.config;
+        // an unknown gradient is reported by the listener
+        let gradient = match pointcloud::gradient(&config.gradient) {
+            Ok(gradient) => gradient,
+            Err(_) => return,
+        };
+        let cells = cloud.cells.read().unwrap();
+        if cells.is_empty() {
+            return;
+        }
+        let values: Vec<f64> = cells.iter().map(|(_, value)| *value).collect();
+        let (min, max) = pointcloud::value_range(&values, config.min_value, config.max_value);
+        let colors = pointcloud::colorize_values(&values, &gradient, Some(min), Some(max));
+        let points: Vec<ColoredPoint> = cells
+            .iter()
+            .zip(colors)
+            .map(|((center, _), color)| {
+                ColoredPoint::new(Some(Point3::new(center.0, center.1, 0.)), Some(color))
+            })
+            .collect();
+        draw_colored_points(ctx, layer, &points, &|_| true, brightness);
+
+        let unit = if config.style == "density" {
+            "points"
+        } else {
+            "m"
+        };
+        let mut legend = vec![Span::raw(format!("{}: {:.2} ", config.topic, min))];
+        for k in 0..LEGEND_WIDTH {
+            let c = gradient.at(k as f64 / (LEGEND_WIDTH - 1) as f64).to_rgba8();
+            legend.push(Span::styled(
+                "█",
+                Style::default().fg(Color::Rgb(c[0], c[1], c[2])),
+            ));
+        }
+        legend.push(Span::raw(format!(" {:.2} {}", max, unit)));
+        // one row per point cloud, from the bottom of the viewport
+        let row_height = (y_bounds[1] - y_bounds[0]) / self.terminal_size.1 as f64;
+        ctx.print(
+            x_bounds[0],
+            y_bounds[0] + i as f64 * row_height,
+            Spans::from(legend),
+        );
+    }
+
+    fn draw_layer(&self, ctx: &mut Context, layer: &Layer, x_bounds: [f64; 2], y_bounds: [f64; 2]) {
+        match layer.kind {
+            LayerKind::Map(i) => {
+                // one sample per braille dot of the canvas, below its title
+                let samples = (
+                    self.terminal_size.0 as usize * 2,
+                    self.terminal_size.1.saturating_sub(1) as usize * 4,
+                );
+                let map_layers = self.listeners.maps[i].sample(x_bounds, y_bounds, samples);
+                let occupied = map_layers.len() - 1;
+                for (j, map_layer) in map_layers.into_iter().enumerate() {
+                    for colored_points in map_layer {
+                        // only the occupied cells are recolored, to keep the map readable
+                        let color = if j == occupied {
+                            layer.recolor(colored_points.color)
+                        } else {
+                            colored_points.color
+                        };
+                        ctx.draw(&Points {
+                            coords: &colored_points.points,
+                            color: color,
+                        });
+                    }
+                    ctx.layer();
+                }
+            }
+            LayerKind::PointCloud(i) => {
+                let pointcloud = &self.listeners.pointclouds[i];
+                if let Some(brightness) = self.data_brightness(
+                    pointcloud.config.timeout,
+                    &pointcloud.config.stale_data,
+                    &pointcloud.health,
+                ) {
+                    let region = Region::new(&pointcloud.config, self.slice_offset);
+                    if pointcloud::is_height_map(&pointcloud.config.style) {
+                        self.draw_height_map(ctx, layer, i, brightness, x_bounds, y_bounds);
+                    } else {
+                        draw_with_trail(
+                            ctx,
+                            layer,
+                            &pointcloud.trail.read().unwrap(),
+                            &pointcloud.points.read().unwrap(),
+                            &|pt| region.contains(&pt.point),
+                            brightness,
+                        );
+                    }
+                }
+            }
+            LayerKind::Markers => {
+                for mut line in self.listeners.markers.get_lines() {
+                    line.color = layer.recolor(line.color);
+                    ctx.draw(&line);
+                }
+                for label in self.listeners.markers.get_labels() {
+                    ctx.print(
+                        label.x,
+                        label.y,
+                        Span::styled(label.text, Style::default().fg(layer.recolor(label.color))),
+                    );
+                }
+            }
+            LayerKind::Laser(i) => {
+                let laser = &self.listeners.lasers[i];
+                if let Some(brightness) = self.data_brightness(
+                    laser.config.timeout,
+                    &laser.config.stale_data,
+                    &laser.health,
+                ) {
+                    for mut ray in laser.free_rays.read().unwrap().clone() {
+                        ray.color =
+                            fade(layer.recolor(ray.color), brightness * FREE_SPACE_BRIGHTNESS);
+                        ctx.draw(&ray);
+                    }
+                    draw_with_trail(
+                        ctx,
+                        layer,
+                        &laser.trail.read().unwrap(),
+                        &laser.points.read().unwrap(),
+                        &|_| true,
+                        brightness,
+                    );
+                }
+            }
+            LayerKind::Robot => {
+                // the robot frame can be missing, e.g. right after seeking in a bag
+                let base_link_pose = match self.transport.lookup_transform(
+                    &self.static_frame,
+                    &self.robot_frame,
+                    rosrust::Time::new(),
+                ) {
+                    Ok(tf) => tf.transform,
+                    Err(_) => return,
+                };
+                for elem in get_current_footprint(&base_link_pose, &self.footprint) {
+                    ctx.draw(&Line {
+                        x1: elem.0,
+                        y1: elem.1,
+                        x2: elem.2,
+                        y2: elem.3,
+                        color: layer.recolor(Color::Blue),
+                    });
+                }
+                for mut line in Viewport::get_frame_lines(&base_link_pose, self.axis_length) {
+                    line.color = layer.recolor(line.color);
+                    ctx.draw(&line);
+                }
+            }
+            LayerKind::PoseStamped(i) => {
+                for mut line in self.listeners.pose_stamped[i].get_lines() {
+                    line.color = layer.recolor(line.color);
+                    ctx.draw(&line);
+                }
+            }
+            LayerKind::Path(i) => {
+                for mut line in self.listeners.paths[i].get_lines() {
+                    line.color = layer.recolor(line.color);
+                    ctx.draw(&line);
+                }
+            }
+            LayerKind::PoseArray(i) => {
+                for mut line in self.listeners.pose_array[i].get_lines() {
+                    line.color = layer.recolor(line.color);
+                    ctx.draw(&line);
+                }
+            }
+        }
+    }
+
     pub fn get_frame_lines(
         tf: &rosrust_msg::geometry_msgs::Transform,
         axis_length: f64,
@@ -132,8 +698,12 @@ impl AppMode for Viewport {
         match input.as_str() {
             input::ZOOM_IN => self.zoom += self.zoom_factor,
             input::ZOOM_OUT => self.zoom -= self.zoom_factor,
+            input::RECORD => self.toggle_recording(),
+            input::SLICE_UP if self.has_slices() => self.slice_offset += SLICE_STEP,
+            input::SLICE_DOWN if self.has_slices() => self.slice_offset -= SLICE_STEP,
             _ => return,
         }
+        self.update_pointcloud_views();
     }
 
     fn get_name(&self) -> String {
@@ -154,6 +724,18 @@ impl AppMode for Viewport {
                 input::ZOOM_OUT.to_string(),
                 "Decreases the zoom.".to_string(),
             ],
+            [
+                input::RECORD.to_string(),
+                "Starts or stops recording the visualized topics to a bag file.".to_string(),
+            ],
+            [
+                input::SLICE_UP.to_string(),
+                "Raises the height slices of the point clouds.".to_string(),
+            ],
+            [
+                input::SLICE_DOWN.to_string(),
+                "Lowers the height slices of the point clouds.".to_string(),
+            ],
         ]
     }
 }
@@ -161,7 +743,7 @@ impl AppMode for Viewport {
 impl UseViewport for Viewport {
     fn x_bounds(&self) -> [f64; 2] {
         let scale_factor = self.terminal_size.0 as f64 / self.terminal_size.1 as f64 * 0.5;
-        let res = self.tf_listener.clone().lookup_transform(
+        let res = self.transport.clone().lookup_transform(
             &self.static_frame,
             &self.robot_frame,
             rosrust::Time::new(),
@@ -184,7 +766,7 @@ impl UseViewport for Viewport {
     }
     fn y_bounds(&self) -> [f64; 2] {
         let scale_factor = self.terminal_size.0 as f64 / self.terminal_size.1 as f64 * 0.5;
-        let res = self.tf_listener.clone().lookup_transform(
+        let res = self.transport.clone().lookup_transform(
             &self.static_frame,
             &self.robot_frame,
             rosrust::Time::new(),
@@ -205,85 +787,24 @@ impl UseViewport for Viewport {
         ]
     }
 
+    /// Returns the stale topics and the recording status, to be appended to the information of
+    /// the modes.
     fn info(&self) -> String {
-        "".to_string()
+        let stale_topics = self.stale_topics();
+        let stale = if stale_topics.is_empty() {
+            "".to_string()
+        } else {
+            format!(", STALE: {}", stale_topics.join(", "))
+        };
+        // the offset is rounded to hide the floating point errors of the steps
+        let slice = if !self.has_slices() || (self.slice_offset / SLICE_STEP).round() == 0. {
+            "".to_string()
+        } else {
+            format!(", Slice offset: {:+.1} m", self.slice_offset)
+        };
+        stale + &slice + &self.record_info()
     }
     fn draw_in_viewport(&self, ctx: &mut Context) {
-        for map in &self.listeners.maps {
-            ctx.draw(&Points {
-                coords: &map.points.read().unwrap(),
-                color: Color::Rgb(map.config.color.r, map.config.color.g, map.config.color.b),
-            });
-        }
-
-        ctx.layer();
-        for pointcloud in &self.listeners.pointclouds {
-            let points = &pointcloud.points.read().unwrap().clone();
-            for pt in points {
-                ctx.draw(&Points {
-                    coords: &[(pt.point.x, pt.point.y)],
-                    color: pt.color,
-                })
-            }
-        }
-
-        ctx.layer();
-        for line in self.listeners.markers.get_lines() {
-            ctx.draw(&line);
-        }
-
-        ctx.layer();
-        for laser in &self.listeners.lasers {
-            ctx.draw(&Points {
-                coords: &laser.points.read().unwrap(),
-                color: Color::Rgb(
-                    laser.config.color.r,
-                    laser.config.color.g,
-                    laser.config.color.b,
-                ),
-            });
-        }
-
-        ctx.layer();
-        let base_link_pose = self
-            .tf_listener
-            .lookup_transform(&self.static_frame, &self.robot_frame, rosrust::Time::new())
-            .unwrap()
-            .transform;
-        get_current_footprint(&base_link_pose, &self.footprint);
-
-        for elem in get_current_footprint(&base_link_pose, &self.footprint) {
-            ctx.draw(&Line {
-                x1: elem.0,
-                y1: elem.1,
-                x2: elem.2,
-                y2: elem.3,
-                color: Color::Blue,
-            });
-        }
-
-        for line in Viewport::get_frame_lines(&base_link_pose, self.axis_length) {
-            ctx.draw(&line);
-        }
-
-        for pose_stamped in &self.listeners.pose_stamped {
-            for line in pose_stamped.get_lines() {
-                ctx.draw(&line);
-            }
-        }
-
-        for path in &self.listeners.paths {
-            for line in path.get_lines() {
-                ctx.draw(&line)
-            }
-        }
-
-        for pose_array in &self.listeners.pose_array {
-            for line in pose_array.get_lines() {
-                ctx.draw(&line);
-            }
-        }
-
-        ctx.layer();
+        self.draw_in_bounds(ctx, self.x_bounds(), self.y_bounds());
     }
 }
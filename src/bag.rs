@@ -0,0 +1,399 @@
+//! Playback of ROS1 bag files on a `MockTransport`.
+//!
+//! Only the index of the bag is read when it is opened, the messages are read from the file when
+//! they are published. A background thread publishes them on the transport following the bag
+//! timestamps, so the listeners and the TF buffer are fed as they would be by a live system.
+
+use crate::tf::TfBuffer;
+use crate::transport::{MockTransport, TopicInfo};
+use rosbag::{ChunkRecord, IndexRecord, MessageRecord, RosBag};
+use rosrust::RosMsg;
+use rosrust_msg::tf2_msgs::TFMessage;
+use std::collections::HashMap;
+use std::error::Error;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const TICK: Duration = Duration::from_millis(10);
+
+/// Position of a message in the bag file.
+struct BagMessage {
+    time: u64,
+    topic: usize,
+    chunk: usize,
+    /// Offset of the message in its decompressed chunk.
+    offset: u32,
+}
+
+/// Messages of the last chunk read, by offset.
+#[derive(Default)]
+struct ChunkCache {
+    chunk: Option<usize>,
+    messages: HashMap<u32, Vec<u8>>,
+}
+
+struct BagData {
+    bag: RosBag,
+    topics: Vec<TopicInfo>,
+    /// Positions of the chunks in the file, and offsets of their messages.
+    chunks: Vec<(u64, Vec<u32>)>,
+    /// Index of the messages, sorted by time.
+    messages: Vec<BagMessage>,
+    start: u64,
+    end: u64,
+    /// Successive messages are mostly in the same chunk, so the messages of the last chunk read
+    /// are kept to not decompress it again for each of them.
+    cache: Mutex<ChunkCache>,
+}
+
+struct PlayerState {
+    playing: bool,
+    speed: f64,
+    /// Index of the next message to publish.
+    next: usize,
+    /// Current time of the playback, in nanoseconds since the epoch.
+    time: u64,
+}
+
+/// Plays a bag file on a `MockTransport`.
+#[derive(Clone)]
+pub struct BagPlayer {
+    bag: Arc<BagData>,
+    transport: MockTransport,
+    state: Arc<Mutex<PlayerState>>,
+    /// Serializes the publications of the playback thread and of the seeks, without holding the
+    /// state while the subscribers process the messages.
+    publishing: Arc<Mutex<()>>,
+    shutdown: Arc<AtomicBool>,
+    thread: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+/// Reads the connections and the message index of the bag.
+fn read_index(path: &Path) -> Result<BagData, Box<dyn Error>> {
+    let bag = RosBag::new(path)?;
+    let mut topics: Vec<TopicInfo> = Vec::new();
+    let mut connections: HashMap<u32, usize> = HashMap::new();
+    let mut chunks = Vec::new();
+    for record in bag.index_records() {
+        match record? {
+            IndexRecord::Connection(conn) => {
+                let idx = match topics.iter().position(|t| t.name == conn.topic) {
+                    Some(idx) => idx,
+                    None => {
+                        topics.push(TopicInfo {
+                            name: conn.topic.to_string(),
+                            datatype: conn.tp.to_string(),
+                        });
+                        topics.len() - 1
+                    }
+                };
+                connections.insert(conn.id, idx);
+            }
+            IndexRecord::ChunkInfo(chunk_info) => chunks.push((chunk_info.chunk_pos, Vec::new())),
+            IndexRecord::IndexData(_) => (),
+        }
+    }
+    let mut messages = Vec::new();
+    for (chunk_idx, (chunk_pos, offsets)) in chunks.iter_mut().enumerate() {
+        // each chunk is followed by the index of its messages
+        let mut records = bag.chunk_records();
+        records.seek(*chunk_pos)?;
+        records.next();
+        for record in records {
+            let index_data = match record? {
+                ChunkRecord::IndexData(index_data) => index_data,
+                ChunkRecord::Chunk(_) => break,
+            };
+            let topic = *connections.get(&index_data.conn_id).ok_or(format!(
+                "Message on unknown connection {}",
+                index_data.conn_id
+            ))?;
+            for entry in index_data.entries() {
+                offsets.push(entry.offset);
+                messages.push(BagMessage {
+                    time: entry.time,
+                    topic: topic,
+                    chunk: chunk_idx,
+                    offset: entry.offset,
+                });
+            }
+        }
+    }
+    if messages.is_empty() {
+        return Err(format!("The bag {:?} does not contain any message", path).into());
+    }
+    messages.sort_by_key(|m| m.time);
+    Ok(BagData {
+        bag: bag,
+        topics: topics,
+        chunks: chunks,
+        start: messages[0].time,
+        end: messages[messages.len() - 1].time,
+        messages: messages,
+        cache: Mutex::new(ChunkCache::default()),
+    })
+}
+
+impl BagData {
+    /// Reads the data of a message from the file.
+    fn read(&self, msg: &BagMessage) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut cache = self.cache.lock().unwrap();
+        if cache.chunk != Some(msg.chunk) {
+            let (chunk_pos, offsets) = &self.chunks[msg.chunk];
+            let mut records = self.bag.chunk_records();
+            records.seek(*chunk_pos)?;
+            let chunk = match records.next() {
+                Some(Ok(ChunkRecord::Chunk(chunk))) => chunk,
+                Some(Err(e)) => return Err(e.into()),
+                _ => return Err(format!("No chunk at position {}", chunk_pos).into()),
+            };
+            cache.chunk = None;
+            cache.messages.clear();
+            let mut chunk_messages = chunk.messages();
+            for offset in offsets {
+                chunk_messages.seek(*offset)?;
+                if let Some(MessageRecord::MessageData(msg_data)) =
+                    chunk_messages.next().transpose()?
+                {
+                    cache.messages.insert(*offset, msg_data.data.to_vec());
+                }
+            }
+            cache.chunk = Some(msg.chunk);
+        }
+        cache
+            .messages
+            .get(&msg.offset)
+            .cloned()
+            .ok_or_else(|| format!("No message at offset {}", msg.offset).into())
+    }
+}
+
+impl BagPlayer {
+    /// Reads the index of the bag, makes its topics known to the transport and starts playing it,
+    /// unless `paused`.
+    pub fn open(
+        path: &Path,
+        transport: MockTransport,
+        paused: bool,
+    ) -> Result<BagPlayer, Box<dyn Error>> {
+        let bag = read_index(path)?;
+        for topic in &bag.topics {
+            transport.add_topic(&topic.name, &topic.datatype);
+        }
+        let player = BagPlayer {
+            state: Arc::new(Mutex::new(PlayerState {
+                playing: !paused,
+                speed: 1.0,
+                next: 0,
+                time: bag.start,
+            })),
+            bag: Arc::new(bag),
+            transport: transport,
+            publishing: Arc::new(Mutex::new(())),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            thread: Arc::new(Mutex::new(None)),
+        };
+        let thread_player = player.clone();
+        *player.thread.lock().unwrap() = Some(thread::spawn(move || thread_player.play()));
+        Ok(player)
+    }
+
+    /// Stops the playback thread and waits for it to finish.
+    pub fn stop(&self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+
+    fn play(&self) {
+        let mut last_tick = Instant::now();
+        while !self.shutdown.load(Ordering::Relaxed) {
+            thread::sleep(TICK);
+            let now = Instant::now();
+            let elapsed = now.duration_since(last_tick);
+            last_tick = now;
+            let _publishing = self.publishing.lock().unwrap();
+            let to_publish = {
+                let mut state = self.state.lock().unwrap();
+                if !state.playing {
+                    continue;
+                }
+                let advance = (elapsed.as_nanos() as f64 * state.speed) as u64;
+                state.time = self.bag.end.min(state.time + advance);
+                let first = state.next;
+                let time = state.time;
+                state.next += self.bag.messages[first..].partition_point(|m| m.time <= time);
+                if state.next == self.bag.messages.len() {
+                    state.playing = false;
+                }
+                first..state.next
+            };
+            for idx in to_publish {
+                self.publish(idx);
+            }
+        }
+    }
+
+    /// Publishes a message, unless it cannot be read from the file.
+    fn publish(&self, idx: usize) {
+        let msg = &self.bag.messages[idx];
+        let topic = &self.bag.topics[msg.topic];
+        if let Ok(data) = self.bag.read(msg) {
+            self.transport
+                .publish_raw(&topic.name, &topic.datatype, &data);
+        }
+    }
+
+    fn is_tf(&self, msg: &BagMessage) -> bool {
+        let name = &self.bag.topics[msg.topic].name;
+        name == "/tf" || name == "/tf_static"
+    }
+
+    /// Returns the transforms of the bag before the given message: all the static transforms, and
+    /// the latest transform of each other frame. Frames which only appear later get their first
+    /// transform, so that the frames can be resolved from the start of the bag.
+    fn transforms_before(&self, next: usize) -> TfBuffer {
+        let mut tf_buffer = TfBuffer::default();
+        for (idx, msg) in self.bag.messages.iter().enumerate() {
+            if !self.is_tf(msg) {
+                continue;
+            }
+            let is_static = self.bag.topics[msg.topic].name == "/tf_static";
+            let transforms = match self.bag.read(msg) {
+                Ok(data) => match TFMessage::decode_slice(&data) {
+                    Ok(tf_msg) => tf_msg.transforms,
+                    Err(_) => continue,
+                },
+                Err(_) => continue,
+            };
+            for transform in transforms {
+                if is_static || idx < next || !tf_buffer.has_frame(&transform.child_frame_id) {
+                    tf_buffer.add(transform, is_static);
+                }
+            }
+        }
+        tf_buffer
+    }
+
+    /// Moves the playback state to the given time, and returns the range of the skipped
+    /// messages, which starts at the beginning of the bag when moving backward.
+    fn jump_to(&self, state: &mut PlayerState, time: u64) -> Range<usize> {
+        let time = time.max(self.bag.start).min(self.bag.end);
+        let first = if time < state.time { 0 } else { state.next };
+        let last = self.bag.messages.partition_point(|m| m.time <= time);
+        state.next = last;
+        state.time = time;
+        first..last
+    }
+
+    /// Brings the listeners to the state of the bag after the skipped messages.
+    ///
+    /// The transforms are updated, and only the latest skipped message of each other topic is
+    /// published. When the skipped messages start at the beginning of the bag, the transforms are
+    /// rebuilt in a new buffer which replaces the current one at once.
+    fn catch_up(&self, skipped: Range<usize>) {
+        if skipped.start == 0 {
+            self.transport
+                .replace_transforms(self.transforms_before(skipped.end));
+        }
+        let mut latest: HashMap<usize, usize> = HashMap::new();
+        for idx in skipped.clone() {
+            let msg = &self.bag.messages[idx];
+            if !self.is_tf(msg) {
+                latest.insert(msg.topic, idx);
+            } else if skipped.start != 0 {
+                if let Ok(data) = self.bag.read(msg) {
+                    let topic = &self.bag.topics[msg.topic];
+                    self.transport.update_transforms(&topic.name, &data);
+                }
+            }
+        }
+        let mut to_publish: Vec<usize> = latest.into_values().collect();
+        to_publish.sort();
+        for idx in to_publish {
+            self.publish(idx);
+        }
+    }
+
+    /// Pauses or resumes the playback. Resuming a finished playback restarts it.
+    pub fn toggle_pause(&self) {
+        let _publishing = self.publishing.lock().unwrap();
+        let skipped = {
+            let mut state = self.state.lock().unwrap();
+            let restart = !state.playing && state.next == self.bag.messages.len();
+            state.playing = !state.playing;
+            if restart {
+                self.jump_to(&mut state, self.bag.start)
+            } else {
+                return;
+            }
+        };
+        self.catch_up(skipped);
+    }
+
+    /// Pauses the playback and publishes the next message.
+    pub fn step(&self) {
+        let _publishing = self.publishing.lock().unwrap();
+        let next = {
+            let mut state = self.state.lock().unwrap();
+            state.playing = false;
+            if state.next == self.bag.messages.len() {
+                return;
+            }
+            state.time = self.bag.messages[state.next].time;
+            state.next += 1;
+            state.next - 1
+        };
+        self.publish(next);
+    }
+
+    /// Moves the playback forward, or backward if negative, by the given number of seconds.
+    pub fn seek(&self, offset: f64) {
+        let _publishing = self.publishing.lock().unwrap();
+        let skipped = {
+            let mut state = self.state.lock().unwrap();
+            let time = (state.time as f64 + offset * 1e9).max(0.0) as u64;
+            self.jump_to(&mut state, time)
+        };
+        self.catch_up(skipped);
+    }
+
+    /// Moves the playback back to the beginning of the bag.
+    pub fn seek_to_start(&self) {
+        let _publishing = self.publishing.lock().unwrap();
+        let skipped = self.jump_to(&mut self.state.lock().unwrap(), self.bag.start);
+        self.catch_up(skipped);
+    }
+
+    pub fn set_speed(&self, speed: f64) {
+        self.state.lock().unwrap().speed = speed;
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.state.lock().unwrap().speed
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.state.lock().unwrap().playing
+    }
+
+    /// Returns true once all the messages of the bag were published.
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().next == self.bag.messages.len()
+    }
+
+    /// Returns the current time, in seconds since the beginning of the bag.
+    pub fn current_time(&self) -> f64 {
+        (self.state.lock().unwrap().time - self.bag.start) as f64 / 1e9
+    }
+
+    /// Returns the duration of the bag, in seconds.
+    pub fn duration(&self) -> f64 {
+        (self.bag.end - self.bag.start) as f64 / 1e9
+    }
+}
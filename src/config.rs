@@ -1,10 +1,11 @@
 use crate::app_modes::input;
 use confy;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 fn default_int() -> i64 {
     0
@@ -14,6 +15,38 @@ fn default_map_threshold() -> i8 {
     1
 }
 
+fn default_map_color_scheme() -> String {
+    "map".to_string()
+}
+
+fn default_map_free_color() -> Option<Color> {
+    Some(Color {
+        r: 60,
+        g: 60,
+        b: 60,
+    })
+}
+
+fn default_map_unknown_color() -> Option<Color> {
+    Some(Color {
+        r: 30,
+        g: 45,
+        b: 60,
+    })
+}
+
+fn default_stale_data() -> String {
+    "dim".to_string()
+}
+
+fn default_pointcloud_style() -> String {
+    "points".to_string()
+}
+
+fn default_gradient() -> String {
+    "turbo".to_string()
+}
+
 fn default_pose_length() -> f64 {
     0.2
 }
@@ -30,26 +63,63 @@ fn color_red() -> Color {
     Color { r: 255, g: 0, b: 0 }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+fn color_random() -> Color {
+    let mut rng = rand::thread_rng();
+    Color {
+        r: rng.gen_range(0..255),
+        g: rng.gen_range(0..255),
+        b: rng.gen_range(0..255),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Color {
     pub r: u8,
     pub b: u8,
     pub g: u8,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ListenerConfig {
     pub topic: String,
+    #[serde(default)]
+    pub hidden_namespaces: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PointCloud2ListenerConfig {
     pub topic: String,
     #[serde(default = "bool::default")]
     pub use_rgb: bool,
+    #[serde(default = "default_pointcloud_style")]
+    pub style: String,
+    #[serde(default)]
+    pub color_field: Option<String>,
+    #[serde(default = "default_gradient")]
+    pub gradient: String,
+    #[serde(default)]
+    pub min_value: Option<f64>,
+    #[serde(default)]
+    pub max_value: Option<f64>,
+    #[serde(default = "bool::default")]
+    pub categorical: bool,
+    #[serde(default)]
+    pub min_z: Option<f64>,
+    #[serde(default)]
+    pub max_z: Option<f64>,
+    #[serde(default)]
+    pub crop_box: Option<[f64; 4]>,
+    #[serde(default)]
+    pub voxel_size: Option<f64>,
+    #[serde(default)]
+    pub timeout: Option<f64>,
+    #[serde(default)]
+    pub stale_data: Option<String>,
+    #[serde(default)]
+    pub decay_time: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PoseListenerConfig {
     pub topic: String,
     pub style: String,
@@ -59,26 +129,177 @@ pub struct PoseListenerConfig {
     pub length: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ImageListenerConfig {
     pub topic: String,
     #[serde(default = "default_int")]
     pub rotation: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ListenerConfigColor {
     pub topic: String,
     pub color: Color,
+    #[serde(default = "bool::default")]
+    pub color_by_intensity: bool,
+    #[serde(default = "default_gradient")]
+    pub gradient: String,
+    #[serde(default)]
+    pub min_intensity: Option<f64>,
+    #[serde(default)]
+    pub max_intensity: Option<f64>,
+    #[serde(default)]
+    pub min_range: Option<f64>,
+    #[serde(default)]
+    pub max_range: Option<f64>,
+    #[serde(default = "bool::default")]
+    pub free_space_rays: bool,
+    #[serde(default)]
+    pub timeout: Option<f64>,
+    #[serde(default)]
+    pub stale_data: Option<String>,
+    #[serde(default)]
+    pub decay_time: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct MapListenerConfig {
     pub topic: String,
     #[serde(default = "color_white")]
     pub color: Color,
     #[serde(default = "default_map_threshold")]
     pub threshold: i8,
+    #[serde(default = "default_map_free_color")]
+    pub free_color: Option<Color>,
+    #[serde(default = "default_map_unknown_color")]
+    pub unknown_color: Option<Color>,
+    #[serde(default = "default_map_color_scheme")]
+    pub color_scheme: String,
+    #[serde(default = "bool::default")]
+    pub subscribe_updates: bool,
+}
+
+/// Config of a listener of a single topic.
+pub trait TopicConfig: Clone {
+    fn topic(&self) -> &String;
+
+    /// Returns the default config of a listener of the given topic.
+    fn with_topic(topic: &str) -> Self;
+}
+
+impl TopicConfig for ListenerConfig {
+    fn topic(&self) -> &String {
+        &self.topic
+    }
+
+    fn with_topic(topic: &str) -> Self {
+        ListenerConfig {
+            topic: topic.to_string(),
+            hidden_namespaces: Vec::new(),
+        }
+    }
+}
+
+impl TopicConfig for PointCloud2ListenerConfig {
+    fn topic(&self) -> &String {
+        &self.topic
+    }
+
+    fn with_topic(topic: &str) -> Self {
+        PointCloud2ListenerConfig {
+            topic: topic.to_string(),
+            use_rgb: false,
+            style: default_pointcloud_style(),
+            color_field: None,
+            gradient: default_gradient(),
+            min_value: None,
+            max_value: None,
+            categorical: false,
+            min_z: None,
+            max_z: None,
+            crop_box: None,
+            voxel_size: None,
+            timeout: None,
+            stale_data: None,
+            decay_time: 0.,
+        }
+    }
+}
+
+impl TopicConfig for PoseListenerConfig {
+    fn topic(&self) -> &String {
+        &self.topic
+    }
+
+    fn with_topic(topic: &str) -> Self {
+        PoseListenerConfig::with_style(topic, "arrow")
+    }
+}
+
+impl PoseListenerConfig {
+    /// Returns the default config of a listener of the given topic, drawn with the given style.
+    pub fn with_style(topic: &str, style: &str) -> Self {
+        PoseListenerConfig {
+            topic: topic.to_string(),
+            style: style.to_string(),
+            color: color_random(),
+            length: default_pose_length(),
+        }
+    }
+}
+
+impl TopicConfig for ImageListenerConfig {
+    fn topic(&self) -> &String {
+        &self.topic
+    }
+
+    fn with_topic(topic: &str) -> Self {
+        ImageListenerConfig {
+            topic: topic.to_string(),
+            rotation: default_int(),
+        }
+    }
+}
+
+impl TopicConfig for ListenerConfigColor {
+    fn topic(&self) -> &String {
+        &self.topic
+    }
+
+    fn with_topic(topic: &str) -> Self {
+        ListenerConfigColor {
+            topic: topic.to_string(),
+            color: color_random(),
+            color_by_intensity: false,
+            gradient: default_gradient(),
+            min_intensity: None,
+            max_intensity: None,
+            min_range: None,
+            max_range: None,
+            free_space_rays: false,
+            timeout: None,
+            stale_data: None,
+            decay_time: 0.,
+        }
+    }
+}
+
+impl TopicConfig for MapListenerConfig {
+    fn topic(&self) -> &String {
+        &self.topic
+    }
+
+    fn with_topic(topic: &str) -> Self {
+        MapListenerConfig {
+            topic: topic.to_string(),
+            color: color_white(),
+            threshold: default_map_threshold(),
+            free_color: default_map_free_color(),
+            unknown_color: default_map_unknown_color(),
+            color_scheme: default_map_color_scheme(),
+            subscribe_updates: false,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -98,6 +319,67 @@ impl Default for TeleopConfig {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExploreConfig {
+    pub default_increment: f64,
+    pub increment_step: f64,
+    pub follow_frames: Vec<String>,
+}
+
+impl Default for ExploreConfig {
+    fn default() -> ExploreConfig {
+        ExploreConfig {
+            default_increment: 0.5,
+            increment_step: 0.1,
+            follow_frames: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlaybackConfig {
+    pub default_increment: f64,
+    pub increment_step: f64,
+}
+
+impl Default for PlaybackConfig {
+    fn default() -> PlaybackConfig {
+        PlaybackConfig {
+            default_increment: 5.0,
+            increment_step: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordConfig {
+    pub directory: String,
+}
+
+impl Default for RecordConfig {
+    fn default() -> RecordConfig {
+        RecordConfig {
+            directory: ".".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HealthConfig {
+    pub stale_timeout: f64,
+    #[serde(default = "default_stale_data")]
+    pub stale_data: String,
+}
+
+impl Default for HealthConfig {
+    fn default() -> HealthConfig {
+        HealthConfig {
+            stale_timeout: 2.0,
+            stale_data: default_stale_data(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TermvizConfig {
     pub fixed_frame: String,
@@ -118,6 +400,14 @@ pub struct TermvizConfig {
     pub zoom_factor: f64,
     pub key_mapping: HashMap<String, String>,
     pub teleop: TeleopConfig,
+    #[serde(default)]
+    pub explore: ExploreConfig,
+    #[serde(default)]
+    pub playback: PlaybackConfig,
+    #[serde(default)]
+    pub record: RecordConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
 }
 
 impl Default for TermvizConfig {
@@ -133,16 +423,32 @@ impl Default for TermvizConfig {
                     g: 255,
                 },
                 threshold: 1,
+                free_color: default_map_free_color(),
+                unknown_color: default_map_unknown_color(),
+                color_scheme: default_map_color_scheme(),
+                subscribe_updates: false,
             }],
             laser_topics: vec![ListenerConfigColor {
                 topic: "scan".to_string(),
                 color: Color { r: 200, b: 0, g: 0 },
+                color_by_intensity: false,
+                gradient: default_gradient(),
+                min_intensity: None,
+                max_intensity: None,
+                min_range: None,
+                max_range: None,
+                free_space_rays: false,
+                timeout: None,
+                stale_data: None,
+                decay_time: 0.,
             }],
             marker_array_topics: vec![ListenerConfig {
                 topic: "marker_array".to_string(),
+                hidden_namespaces: Vec::new(),
             }],
             marker_topics: vec![ListenerConfig {
                 topic: "marker".to_string(),
+                hidden_namespaces: Vec::new(),
             }],
             image_topics: vec![ImageListenerConfig {
                 topic: "image_rect".to_string(),
@@ -169,6 +475,19 @@ impl Default for TermvizConfig {
             pointcloud2_topics: vec![PointCloud2ListenerConfig {
                 topic: "pointcloud2".to_string(),
                 use_rgb: false,
+                style: default_pointcloud_style(),
+                color_field: None,
+                gradient: default_gradient(),
+                min_value: None,
+                max_value: None,
+                categorical: false,
+                min_z: None,
+                max_z: None,
+                crop_box: None,
+                voxel_size: None,
+                timeout: None,
+                stale_data: None,
+                decay_time: 0.,
             }],
             send_pose_topic: "initialpose".to_string(),
             target_framerate: 30,
@@ -191,8 +510,19 @@ impl Default for TermvizConfig {
                 (input::SHOW_HELP.to_string(), "h".to_string()),
                 (input::MODE_2.to_string(), "t".to_string()),
                 (input::MODE_3.to_string(), "i".to_string()),
+                (input::PLAYBACK_MODE.to_string(), "p".to_string()),
+                (input::SAVE_CONFIG.to_string(), "S".to_string()),
+                (input::FOLLOW_FRAME.to_string(), "f".to_string()),
+                (input::STEP_FORWARD.to_string(), "n".to_string()),
+                (input::RECORD.to_string(), "r".to_string()),
+                (input::SLICE_UP.to_string(), "]".to_string()),
+                (input::SLICE_DOWN.to_string(), "[".to_string()),
             ]),
             teleop: TeleopConfig::default(),
+            explore: ExploreConfig::default(),
+            playback: PlaybackConfig::default(),
+            record: RecordConfig::default(),
+            health: HealthConfig::default(),
         }
     }
 }
@@ -209,12 +539,18 @@ pub fn ask_store() -> bool {
     }
 }
 
-pub fn get_config(config_path: Option<&String>) -> Result<TermvizConfig, confy::ConfyError> {
+/// Loads the config, and returns it with the path of the file it was loaded from, which is also
+/// where it is saved. The default config is saved to the user config file.
+pub fn get_config(
+    config_path: Option<&String>,
+) -> Result<(TermvizConfig, PathBuf), confy::ConfyError> {
     let mut cfg = TermvizConfig::default();
     let user_path = confy::get_configuration_file_path("termviz", "termviz")?;
-    if config_path.is_some() {
+    let mut path = user_path.clone();
+    if let Some(config_path) = config_path {
         // config path provided by command line arg
-        cfg = confy::load_path(config_path.unwrap()).unwrap();
+        cfg = confy::load_path(config_path).unwrap();
+        path = PathBuf::from(config_path);
     } else if Path::new(&user_path).exists() {
         // use user config if exists
         cfg = confy::load("termviz", "termviz")?;
@@ -223,6 +559,7 @@ pub fn get_config(config_path: Option<&String>) -> Result<TermvizConfig, confy::
         let sys_path = "/etc/termviz/termviz.yml";
         if Path::new(sys_path).exists() {
             cfg = confy::load_path(sys_path)?;
+            path = PathBuf::from(sys_path);
         } else {
             // no config found, generate default
             println!("No config found, using default");
@@ -236,5 +573,16 @@ pub fn get_config(config_path: Option<&String>) -> Result<TermvizConfig, confy::
             }
         }
     };
-    Ok(cfg)
+    add_missing_keys(&mut cfg);
+    Ok((cfg, path))
+}
+
+/// Binds the inputs missing from the key mapping, e.g. inputs added after the config was stored,
+/// to their default key if it is not used by another input.
+fn add_missing_keys(cfg: &mut TermvizConfig) {
+    for (input, key) in TermvizConfig::default().key_mapping {
+        if !cfg.key_mapping.contains_key(&input) && !cfg.key_mapping.values().any(|k| *k == key) {
+            cfg.key_mapping.insert(input, key);
+        }
+    }
 }
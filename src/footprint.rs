@@ -15,6 +15,9 @@ pub fn get_default_footprint() -> Vec<(f64, f64)> {
 }
 
 pub fn get_footprint() -> Vec<(f64, f64)> {
+    if !rosrust::is_initialized() {
+        return get_default_footprint();
+    }
     let param = rosrust::param("/footprint");
     let mut result = Vec::<(f64, f64)>::new();
     match param {
@@ -0,0 +1,121 @@
+//! Reception statistics of the topics, to tell apart a topic that stopped publishing from one
+//! publishing empty data.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Duration over which the receive rate is computed.
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+struct Stats {
+    count: u64,
+    /// Reception times of the messages received during the rate window.
+    received: VecDeque<Instant>,
+    latency: Option<f64>,
+    /// Error of the last message which could not be read.
+    error: Option<String>,
+}
+
+/// Statistics of the messages received on a topic, shared with the callback of its listener.
+#[derive(Clone)]
+pub struct TopicHealth {
+    stats: Arc<Mutex<Stats>>,
+    latched: bool,
+}
+
+impl TopicHealth {
+    /// Creates the statistics of a topic.
+    ///
+    /// Latched topics, e.g. maps, are usually published once, so they are only considered stale
+    /// until their first message.
+    pub fn new(latched: bool) -> TopicHealth {
+        TopicHealth {
+            stats: Arc::new(Mutex::new(Stats {
+                count: 0,
+                received: VecDeque::new(),
+                latency: None,
+                error: None,
+            })),
+            latched: latched,
+        }
+    }
+
+    /// Records a message, with the stamp of its header if it has one.
+    pub fn record(&self, stamp: Option<rosrust::Time>) {
+        let now = Instant::now();
+        let mut stats = self.stats.lock().unwrap();
+        stats.count += 1;
+        stats.received.push_back(now);
+        while stats.received.len() > 1
+            && now.duration_since(*stats.received.front().unwrap()) > RATE_WINDOW
+        {
+            stats.received.pop_front();
+        }
+        // messages without stamp keep the latency of the previous ones
+        if let Some(stamp) = stamp.filter(|s| s.nanos() != 0) {
+            let wall_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos() as i64;
+            stats.latency = Some((wall_time - stamp.nanos()) as f64 / 1e9);
+        }
+    }
+
+    /// Sets the error of the last message, or clears it if the message could be read.
+    pub fn set_error(&self, error: Option<String>) {
+        self.stats.lock().unwrap().error = error;
+    }
+
+    /// Returns the error of the last message, if it could not be read.
+    pub fn error(&self) -> Option<String> {
+        self.stats.lock().unwrap().error.clone()
+    }
+
+    /// Returns the number of received messages.
+    pub fn count(&self) -> u64 {
+        self.stats.lock().unwrap().count
+    }
+
+    /// Returns the receive rate in Hz, over the last seconds.
+    pub fn rate(&self) -> f64 {
+        let stats = self.stats.lock().unwrap();
+        let now = Instant::now();
+        let recent: Vec<&Instant> = stats
+            .received
+            .iter()
+            .filter(|t| now.duration_since(**t) <= RATE_WINDOW)
+            .collect();
+        if recent.len() < 2 {
+            return 0.;
+        }
+        let span = recent[recent.len() - 1].duration_since(*recent[0]);
+        (recent.len() - 1) as f64 / span.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Returns the delay, in seconds, between the stamp of the last stamped message and its
+    /// reception.
+    pub fn latency(&self) -> Option<f64> {
+        self.stats.lock().unwrap().latency
+    }
+
+    /// Returns the time, in seconds, since the last message was received.
+    pub fn last_seen(&self) -> Option<f64> {
+        self.stats
+            .lock()
+            .unwrap()
+            .received
+            .back()
+            .map(|t| t.elapsed().as_secs_f64())
+    }
+
+    /// Returns true if no message was received yet, or if the last one is older than the
+    /// timeout, in seconds, for a topic that is not latched.
+    pub fn is_stale(&self, timeout: f64) -> bool {
+        match self.last_seen() {
+            None => true,
+            Some(_) if self.latched => false,
+            Some(age) => age > timeout,
+        }
+    }
+}
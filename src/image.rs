@@ -1,7 +1,7 @@
 use crate::config::ImageListenerConfig;
+use crate::transport::{Subscription, Transport};
 use byteorder::{ByteOrder, LittleEndian};
 use image::{imageops, DynamicImage, ImageBuffer, RgbaImage};
-use rosrust;
 use rosrust_msg;
 use std::sync::{Arc, RwLock};
 
@@ -73,12 +73,13 @@ fn read_u16(vec: &Vec<u8>) -> Vec<u8> {
 pub struct ImageListener {
     pub config: ImageListenerConfig,
     pub img: Arc<RwLock<RgbaImage>>,
-    _subscriber: Option<rosrust::Subscriber>,
+    _subscriber: Option<Subscription>,
     _rotation: Arc<RwLock<i64>>,
+    _transport: Arc<dyn Transport>,
 }
 
 impl ImageListener {
-    pub fn new(config: ImageListenerConfig) -> ImageListener {
+    pub fn new(config: ImageListenerConfig, transport: Arc<dyn Transport>) -> ImageListener {
         let img = Arc::new(RwLock::new(RgbaImage::new(0, 0)));
         let default_rotation = config.rotation.clone();
         ImageListener {
@@ -86,29 +87,41 @@ impl ImageListener {
             img,
             _subscriber: None,
             _rotation: Arc::new(RwLock::new(default_rotation)),
+            _transport: transport,
         }
     }
 
+    /// Updates the config of the listener of the same topic, keeping its image. The rotation
+    /// is only reset if the configured one changed.
+    pub fn set_config(&mut self, config: ImageListenerConfig) {
+        if config.rotation != self.config.rotation {
+            *self._rotation.write().unwrap() = config.rotation;
+        }
+        self.config = config;
+    }
+
     pub fn setup_sub(&mut self) {
         let cb_img = self.img.clone();
         let cb_rotation = self._rotation.clone();
-        let sub = rosrust::subscribe(
-            &self.config.topic,
-            1,
-            move |img_msg: rosrust_msg::sensor_msgs::Image| {
-                let mut img = read_img_msg(img_msg).to_rgba8();
-                let rot = cb_rotation.read().unwrap();
-                match *rot {
-                    90 => img = imageops::rotate90(&img),
-                    180 => img = imageops::rotate180(&img),
-                    270 => img = imageops::rotate270(&img),
-                    _ => (),
-                }
-                let mut cb_img = cb_img.write().unwrap();
-                *cb_img = img;
-            },
-        )
-        .unwrap();
+        let sub = self
+            ._transport
+            .subscribe(
+                &self.config.topic,
+                1,
+                move |img_msg: rosrust_msg::sensor_msgs::Image| {
+                    let mut img = read_img_msg(img_msg).to_rgba8();
+                    let rot = cb_rotation.read().unwrap();
+                    match *rot {
+                        90 => img = imageops::rotate90(&img),
+                        180 => img = imageops::rotate180(&img),
+                        270 => img = imageops::rotate270(&img),
+                        _ => (),
+                    }
+                    let mut cb_img = cb_img.write().unwrap();
+                    *cb_img = img;
+                },
+            )
+            .unwrap();
         self._subscriber = Some(sub)
     }
 
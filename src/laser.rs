@@ -1,68 +1,186 @@
 use crate::config::ListenerConfigColor;
+use crate::health::TopicHealth;
+use crate::pointcloud::{colorize_values, gradient, ColoredPoint};
+use crate::trail::Trail;
 use crate::transformation;
+use crate::transport::{Subscription, Transport};
+use nalgebra::geometry::Point3;
 use std::sync::{Arc, RwLock};
+use tui::style::Color;
+use tui::widgets::canvas::Line;
 
-use rosrust;
-use rustros_tf;
+/// Kind of the return of a beam of a scan.
+#[derive(Debug, PartialEq)]
+pub enum Return {
+    /// The beam hit an obstacle at the range.
+    Hit,
+    /// The beam did not hit any obstacle up to the maximum range.
+    Free,
+    /// The range is not drawn, e.g. NaN or outside the range limits.
+    Invalid,
+}
+
+/// Classifies a range according to the conventions of sensor_msgs/LaserScan, given the maximum
+/// range of the scan and the range limits of the scan intersected with the configured ones.
+pub fn classify_range(range: f32, scan_range_max: f32, min_range: f64, max_range: f64) -> Return {
+    if range == f32::INFINITY || range >= scan_range_max {
+        // drivers report the maximum range, or +inf, for beams without return
+        return Return::Free;
+    }
+    let range = range as f64;
+    if range.is_nan() || range < min_range || range > max_range {
+        Return::Invalid
+    } else {
+        Return::Hit
+    }
+}
 
 pub struct LaserListener {
     pub config: ListenerConfigColor,
-    pub points: Arc<RwLock<Vec<(f64, f64)>>>,
-    _tf_listener: Arc<rustros_tf::TfListener>,
+    pub points: Arc<RwLock<Vec<ColoredPoint>>>,
+    pub trail: Arc<RwLock<Trail<Vec<ColoredPoint>>>>,
+    pub free_rays: Arc<RwLock<Vec<Line>>>,
+    pub health: TopicHealth,
+    /// Config read by the subscriber for each message.
+    cb_config: Arc<RwLock<ListenerConfigColor>>,
+    _transport: Arc<dyn Transport>,
     _static_frame: String,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: Subscription,
 }
 
 impl LaserListener {
     pub fn new(
         config: ListenerConfigColor,
-        tf_listener: Arc<rustros_tf::TfListener>,
+        transport: Arc<dyn Transport>,
         static_frame: String,
     ) -> LaserListener {
-        let scan_points = Arc::new(RwLock::new(Vec::<(f64, f64)>::new()));
+        let scan_points = Arc::new(RwLock::new(Vec::<ColoredPoint>::new()));
         let cb_scan_points = scan_points.clone();
+        let trail = Arc::new(RwLock::new(Trail::new(config.decay_time)));
+        let cb_trail = trail.clone();
+        let free_rays = Arc::new(RwLock::new(Vec::<Line>::new()));
+        let cb_free_rays = free_rays.clone();
         let str_ = static_frame.clone();
+        let health = TopicHealth::new(false);
+        let cb_health = health.clone();
+        let shared_config = Arc::new(RwLock::new(config.clone()));
+        let cb_shared_config = shared_config.clone();
 
-        let local_listener = tf_listener.clone();
-        let laser_sub = rosrust::subscribe(
-            &config.topic,
-            2,
-            move |scan: rosrust_msg::sensor_msgs::LaserScan| {
-                let mut points: Vec<(f64, f64)> = Vec::new();
-                let res = local_listener.lookup_transform(
-                    &str_,
-                    &scan.header.frame_id,
-                    scan.header.stamp,
-                );
-                match &res {
-                    Ok(res) => res,
-                    Err(_e) => return,
-                };
-                for (i, range) in scan.ranges.iter().enumerate() {
-                    let angle = scan.angle_min + i as f32 * scan.angle_increment;
-                    let pt = transformation::transform_relative_pt(
-                        &res.as_ref().unwrap().transform,
-                        (
-                            *range as f64 * angle.cos() as f64,
-                            *range as f64 * angle.sin() as f64,
-                        ),
+        let local_transport = transport.clone();
+        let laser_sub = transport
+            .subscribe(
+                &config.topic,
+                2,
+                move |scan: rosrust_msg::sensor_msgs::LaserScan| {
+                    cb_health.record(Some(scan.header.stamp));
+                    let cb_config = cb_shared_config.read().unwrap().clone();
+                    let gradient = match gradient(&cb_config.gradient) {
+                        Ok(gradient) => gradient,
+                        Err(e) => {
+                            cb_health.set_error(Some(e));
+                            return;
+                        }
+                    };
+                    cb_health.set_error(None);
+                    let color = Color::Rgb(cb_config.color.r, cb_config.color.g, cb_config.color.b);
+                    let mut points: Vec<(f64, f64)> = Vec::new();
+                    let mut intensities: Vec<f64> = Vec::new();
+                    let mut rays: Vec<Line> = Vec::new();
+                    let res = local_transport.lookup_transform(
+                        &str_,
+                        &scan.header.frame_id,
+                        scan.header.stamp,
                     );
-                    if range > &scan.range_min {
-                        points.push(pt);
+                    match &res {
+                        Ok(res) => res,
+                        Err(_e) => return,
+                    };
+                    let transform = &res.as_ref().unwrap().transform;
+                    let origin = transformation::transform_relative_pt(transform, (0., 0.));
+                    let min_range = cb_config
+                        .min_range
+                        .map_or(scan.range_min as f64, |r| r.max(scan.range_min as f64));
+                    let max_range = cb_config
+                        .max_range
+                        .map_or(scan.range_max as f64, |r| r.min(scan.range_max as f64));
+                    for (i, range) in scan.ranges.iter().enumerate() {
+                        let angle = (scan.angle_min + i as f32 * scan.angle_increment) as f64;
+                        let beam_pt = |range: f64| {
+                            transformation::transform_relative_pt(
+                                transform,
+                                (range * angle.cos(), range * angle.sin()),
+                            )
+                        };
+                        match classify_range(*range, scan.range_max, min_range, max_range) {
+                            Return::Hit => {
+                                points.push(beam_pt(*range as f64));
+                                if let Some(intensity) = scan.intensities.get(i) {
+                                    intensities.push(*intensity as f64);
+                                }
+                            }
+                            Return::Free if cb_config.free_space_rays => {
+                                let end = beam_pt(max_range);
+                                rays.push(Line {
+                                    x1: origin.0,
+                                    y1: origin.1,
+                                    x2: end.0,
+                                    y2: end.1,
+                                    color: color,
+                                });
+                            }
+                            _ => (),
+                        }
+                    }
+                    // scans without an intensity per range keep the configured color
+                    let colors =
+                        if cb_config.color_by_intensity && intensities.len() == points.len() {
+                            colorize_values(
+                                &intensities,
+                                &gradient,
+                                cb_config.min_intensity,
+                                cb_config.max_intensity,
+                            )
+                        } else {
+                            vec![color; points.len()]
+                        };
+                    let points: Vec<ColoredPoint> = points
+                        .into_iter()
+                        .zip(colors)
+                        .map(|((x, y), color)| {
+                            ColoredPoint::new(Some(Point3::new(x, y, 0.)), Some(color))
+                        })
+                        .collect();
+                    let mut cb_trail = cb_trail.write().unwrap();
+                    if cb_trail.is_enabled() {
+                        cb_trail.push(points.clone());
                     }
-                }
-                let mut cb_scan_points = cb_scan_points.write().unwrap();
-                *cb_scan_points = points;
-            },
-        )
-        .unwrap();
+                    *cb_free_rays.write().unwrap() = rays;
+                    let mut cb_scan_points = cb_scan_points.write().unwrap();
+                    *cb_scan_points = points;
+                },
+            )
+            .unwrap();
 
         LaserListener {
             config,
             points: scan_points,
-            _tf_listener: tf_listener.clone(),
+            trail: trail,
+            free_rays: free_rays,
+            health: health,
+            cb_config: shared_config,
+            _transport: transport.clone(),
             _static_frame: static_frame.to_string(),
             _subscriber: laser_sub,
         }
     }
+
+    /// Updates the config of the listener of the same topic, keeping its data.
+    pub fn set_config(&mut self, config: ListenerConfigColor) {
+        self.trail
+            .write()
+            .unwrap()
+            .set_decay_time(config.decay_time);
+        *self.cb_config.write().unwrap() = config.clone();
+        self.config = config;
+    }
 }
@@ -1,15 +1,123 @@
 use crate::config::{
     ListenerConfig, ListenerConfigColor, MapListenerConfig, PointCloud2ListenerConfig,
-    PoseListenerConfig,
+    PoseListenerConfig, TermvizConfig, TopicConfig,
 };
+use crate::health::TopicHealth;
 use crate::laser;
 use crate::map;
 use crate::marker;
 use crate::pointcloud;
 use crate::pose;
+use crate::transport::{MessageDescription, Transport};
 
 use std::sync::Arc;
 
+/// A kind of listener, with the ROS type of its topics and the list of its configs.
+pub struct ListenerKind {
+    pub datatype: &'static str,
+    /// Returns the configured topics of this kind.
+    pub topics: fn(&TermvizConfig) -> Vec<String>,
+    /// Sets the configured topics of this kind.
+    ///
+    /// Topics that were already configured keep their config, the others get a default one.
+    pub set_topics: fn(&mut TermvizConfig, &[String]),
+}
+
+fn config_topics<C: TopicConfig>(configs: &[C]) -> Vec<String> {
+    configs.iter().map(|c| c.topic().clone()).collect()
+}
+
+fn set_config_topics<C: TopicConfig>(configs: &mut Vec<C>, topics: &[String]) {
+    set_config_topics_with(configs, topics, C::with_topic);
+}
+
+/// Sets the topics of the configs, the new topics getting the config returned by the function.
+fn set_config_topics_with<C: TopicConfig>(
+    configs: &mut Vec<C>,
+    topics: &[String],
+    new_config: impl Fn(&str) -> C,
+) {
+    *configs = topics
+        .iter()
+        .map(|topic| {
+            configs
+                .iter()
+                .find(|c| c.topic() == topic)
+                .cloned()
+                .unwrap_or_else(|| new_config(topic))
+        })
+        .collect();
+}
+
+/// Sets the topics of the pose configs, the new topics getting the default style of their kind.
+fn set_pose_topics(configs: &mut Vec<PoseListenerConfig>, topics: &[String], styles: &[&str]) {
+    set_config_topics_with(configs, topics, |topic| {
+        PoseListenerConfig::with_style(topic, styles[0])
+    });
+}
+
+/// Returns all the kinds of listeners that can be configured.
+pub fn listener_kinds() -> Vec<ListenerKind> {
+    vec![
+        ListenerKind {
+            datatype: "geometry_msgs/PoseArray",
+            topics: |c| config_topics(&c.pose_array_topics),
+            set_topics: |c, t| {
+                set_pose_topics(&mut c.pose_array_topics, t, &pose::POSE_ARRAY_STYLES)
+            },
+        },
+        ListenerKind {
+            datatype: "geometry_msgs/PoseStamped",
+            topics: |c| config_topics(&c.pose_stamped_topics),
+            set_topics: |c, t| {
+                set_pose_topics(&mut c.pose_stamped_topics, t, &pose::POSE_STAMPED_STYLES)
+            },
+        },
+        ListenerKind {
+            datatype: "nav_msgs/OccupancyGrid",
+            topics: |c| config_topics(&c.map_topics),
+            set_topics: |c, t| set_config_topics(&mut c.map_topics, t),
+        },
+        ListenerKind {
+            datatype: "nav_msgs/Path",
+            topics: |c| config_topics(&c.path_topics),
+            set_topics: |c, t| set_pose_topics(&mut c.path_topics, t, &pose::PATH_STYLES),
+        },
+        ListenerKind {
+            datatype: "sensor_msgs/Image",
+            topics: |c| config_topics(&c.image_topics),
+            set_topics: |c, t| set_config_topics(&mut c.image_topics, t),
+        },
+        ListenerKind {
+            datatype: "sensor_msgs/LaserScan",
+            topics: |c| config_topics(&c.laser_topics),
+            set_topics: |c, t| set_config_topics(&mut c.laser_topics, t),
+        },
+        ListenerKind {
+            datatype: "sensor_msgs/PointCloud2",
+            topics: |c| config_topics(&c.pointcloud2_topics),
+            set_topics: |c, t| set_config_topics(&mut c.pointcloud2_topics, t),
+        },
+        ListenerKind {
+            datatype: "visualization_msgs/Marker",
+            topics: |c| config_topics(&c.marker_topics),
+            set_topics: |c, t| set_config_topics(&mut c.marker_topics, t),
+        },
+        ListenerKind {
+            datatype: "visualization_msgs/MarkerArray",
+            topics: |c| config_topics(&c.marker_array_topics),
+            set_topics: |c, t| set_config_topics(&mut c.marker_array_topics, t),
+        },
+    ]
+}
+
+/// Statistics of the messages received by a listener.
+pub struct TopicStatus {
+    pub topic: String,
+    pub datatype: String,
+    pub health: TopicHealth,
+}
+
 pub struct Listeners {
     pub lasers: Vec<laser::LaserListener>,
     pub markers: marker::MarkersListener,
@@ -18,11 +126,38 @@ pub struct Listeners {
     pub pose_array: Vec<pose::PoseArrayListener>,
     pub pointclouds: Vec<pointcloud::PointCloud2Listener>,
     pub paths: Vec<pose::PathListener>,
+    transport: Arc<dyn Transport>,
+    static_frame: String,
+}
+
+/// Keeps the listeners of the topics which are still configured, in the order of the configs,
+/// with their config updated, and creates the listeners of the new topics.
+pub fn sync_listeners<C: TopicConfig, L>(
+    listeners: &mut Vec<L>,
+    configs: Vec<C>,
+    listener_config: impl Fn(&L) -> &C,
+    mut set_config: impl FnMut(&mut L, C),
+    mut create: impl FnMut(C) -> L,
+) {
+    let mut previous: Vec<L> = listeners.drain(..).collect();
+    for config in configs {
+        match previous
+            .iter()
+            .position(|l| listener_config(l).topic() == config.topic())
+        {
+            Some(idx) => {
+                let mut listener = previous.remove(idx);
+                set_config(&mut listener, config);
+                listeners.push(listener);
+            }
+            None => listeners.push(create(config)),
+        }
+    }
 }
 
 impl Listeners {
     pub fn new(
-        tf_listener: Arc<rustros_tf::TfListener>,
+        transport: Arc<dyn Transport>,
         static_frame: String,
         laser_topics: Vec<ListenerConfigColor>,
         marker_topics: Vec<ListenerConfig>,
@@ -37,12 +172,12 @@ impl Listeners {
         for laser_config in laser_topics {
             lasers.push(laser::LaserListener::new(
                 laser_config,
-                tf_listener.clone(),
+                transport.clone(),
                 static_frame.clone(),
             ));
         }
 
-        let mut markers = marker::MarkersListener::new(tf_listener.clone(), static_frame.clone());
+        let mut markers = marker::MarkersListener::new(transport.clone(), static_frame.clone());
         for marker_config in marker_topics {
             markers.add_marker_listener(&marker_config);
         }
@@ -55,7 +190,7 @@ impl Listeners {
         for map_config in map_topics {
             maps.push(map::MapListener::new(
                 map_config,
-                tf_listener.clone(),
+                transport.clone(),
                 static_frame.clone(),
             ));
         }
@@ -64,22 +199,22 @@ impl Listeners {
         for pc_config in pointcloud2_topics {
             pointclouds.push(pointcloud::PointCloud2Listener::new(
                 pc_config,
-                tf_listener.clone(),
+                transport.clone(),
                 static_frame.clone(),
             ));
         }
 
         let pose_stamped = pose_stamped_topics
             .into_iter()
-            .map(|topic| pose::PoseStampedListener::new(topic))
+            .map(|topic| pose::PoseStampedListener::new(topic, transport.clone()))
             .collect();
         let pose_array = pose_array_topics
             .into_iter()
-            .map(|topic| pose::PoseArrayListener::new(topic))
+            .map(|topic| pose::PoseArrayListener::new(topic, transport.clone()))
             .collect();
         let paths = path_topics
             .into_iter()
-            .map(|topic| pose::PathListener::new(topic))
+            .map(|topic| pose::PathListener::new(topic, transport.clone()))
             .collect();
         Listeners {
             lasers,
@@ -89,6 +224,204 @@ impl Listeners {
             pose_array,
             pointclouds,
             paths,
+            transport,
+            static_frame,
+        }
+    }
+
+    /// Updates the listeners to the given topics.
+    ///
+    /// Listeners of the topics which are still configured are kept with their data and get
+    /// their new config, the others are dropped, and listeners are created for the new topics.
+    pub fn update(
+        &mut self,
+        laser_topics: Vec<ListenerConfigColor>,
+        marker_topics: Vec<ListenerConfig>,
+        marker_array_topics: Vec<ListenerConfig>,
+        map_topics: Vec<MapListenerConfig>,
+        pose_stamped_topics: Vec<PoseListenerConfig>,
+        pose_array_topics: Vec<PoseListenerConfig>,
+        pointcloud2_topics: Vec<PointCloud2ListenerConfig>,
+        path_topics: Vec<PoseListenerConfig>,
+    ) {
+        let transport = self.transport.clone();
+        let static_frame = self.static_frame.clone();
+        sync_listeners(
+            &mut self.lasers,
+            laser_topics,
+            |l| &l.config,
+            |l, c| l.set_config(c),
+            |c| laser::LaserListener::new(c, transport.clone(), static_frame.clone()),
+        );
+        sync_listeners(
+            &mut self.maps,
+            map_topics,
+            |l| &l.config,
+            |l, c| {
+                // the updates are subscribed with the map
+                if l.config.subscribe_updates == c.subscribe_updates {
+                    l.config = c;
+                } else {
+                    *l = map::MapListener::new(c, transport.clone(), static_frame.clone());
+                }
+            },
+            |c| map::MapListener::new(c, transport.clone(), static_frame.clone()),
+        );
+        sync_listeners(
+            &mut self.pointclouds,
+            pointcloud2_topics,
+            |l| &l.config,
+            |l, c| l.set_config(c),
+            |c| pointcloud::PointCloud2Listener::new(c, transport.clone(), static_frame.clone()),
+        );
+        sync_listeners(
+            &mut self.pose_stamped,
+            pose_stamped_topics,
+            |l| &l.config,
+            |l, c| l.config = c,
+            |c| pose::PoseStampedListener::new(c, transport.clone()),
+        );
+        sync_listeners(
+            &mut self.pose_array,
+            pose_array_topics,
+            |l| &l.config,
+            |l, c| l.config = c,
+            |c| pose::PoseArrayListener::new(c, transport.clone()),
+        );
+        sync_listeners(
+            &mut self.paths,
+            path_topics,
+            |l| &l.config,
+            |l, c| l.config = c,
+            |c| pose::PathListener::new(c, transport.clone()),
+        );
+
+        let active_markers: Vec<String> = self
+            .markers
+            .topics()
+            .into_iter()
+            .map(|(topic, _)| topic)
+            .collect();
+        for topic in &active_markers {
+            if !marker_topics
+                .iter()
+                .chain(marker_array_topics.iter())
+                .any(|c| &c.topic == topic)
+            {
+                self.markers.remove_listener(topic);
+            }
+        }
+        for config in marker_topics
+            .iter()
+            .filter(|c| !active_markers.contains(&c.topic))
+        {
+            self.markers.add_marker_listener(config);
+        }
+        for config in marker_array_topics
+            .iter()
+            .filter(|c| !active_markers.contains(&c.topic))
+        {
+            self.markers.add_marker_array_listener(config);
+        }
+    }
+
+    /// Returns the statistics of the messages received by all the listeners.
+    pub fn statuses(&self) -> Vec<TopicStatus> {
+        let mut statuses = Vec::new();
+        let mut add = |topic: &String, datatype: &str, health: &TopicHealth| {
+            statuses.push(TopicStatus {
+                topic: topic.clone(),
+                datatype: datatype.to_string(),
+                health: health.clone(),
+            })
+        };
+        for map in &self.maps {
+            add(&map.config.topic, "nav_msgs/OccupancyGrid", &map.health);
+        }
+        for pointcloud in &self.pointclouds {
+            add(
+                &pointcloud.config.topic,
+                "sensor_msgs/PointCloud2",
+                &pointcloud.health,
+            );
+        }
+        for ((topic, description), (_, health)) in self
+            .markers
+            .topics()
+            .into_iter()
+            .zip(self.markers.healths())
+        {
+            add(&topic, &description.datatype, &health);
+        }
+        for laser in &self.lasers {
+            add(&laser.config.topic, "sensor_msgs/LaserScan", &laser.health);
+        }
+        for pose in &self.pose_stamped {
+            add(
+                &pose.config.topic,
+                "geometry_msgs/PoseStamped",
+                &pose.health,
+            );
+        }
+        for path in &self.paths {
+            add(&path.config.topic, "nav_msgs/Path", &path.health);
+        }
+        for poses in &self.pose_array {
+            add(
+                &poses.config.topic,
+                "geometry_msgs/PoseArray",
+                &poses.health,
+            );
+        }
+        statuses
+    }
+
+    /// Returns the topics subscribed by all the listeners, with the description of their messages.
+    pub fn topics(&self) -> Vec<(String, MessageDescription)> {
+        let mut topics = Vec::new();
+        for laser in &self.lasers {
+            topics.push((
+                laser.config.topic.clone(),
+                MessageDescription::of::<rosrust_msg::sensor_msgs::LaserScan>(),
+            ));
+        }
+        topics.extend(self.markers.topics());
+        for map in &self.maps {
+            topics.push((
+                map.config.topic.clone(),
+                MessageDescription::of::<rosrust_msg::nav_msgs::OccupancyGrid>(),
+            ));
+            if map.config.subscribe_updates {
+                topics.push((
+                    format!("{}_updates", map.config.topic),
+                    MessageDescription::of::<rosrust_msg::map_msgs::OccupancyGridUpdate>(),
+                ));
+            }
+        }
+        for pose in &self.pose_stamped {
+            topics.push((
+                pose.config.topic.clone(),
+                MessageDescription::of::<rosrust_msg::geometry_msgs::PoseStamped>(),
+            ));
+        }
+        for poses in &self.pose_array {
+            topics.push((
+                poses.config.topic.clone(),
+                MessageDescription::of::<rosrust_msg::geometry_msgs::PoseArray>(),
+            ));
+        }
+        for pointcloud in &self.pointclouds {
+            topics.push((
+                pointcloud.config.topic.clone(),
+                MessageDescription::of::<rosrust_msg::sensor_msgs::PointCloud2>(),
+            ));
+        }
+        for path in &self.paths {
+            topics.push((
+                path.config.topic.clone(),
+                MessageDescription::of::<rosrust_msg::nav_msgs::Path>(),
+            ));
         }
+        topics
     }
 }
@@ -1,7 +1,9 @@
 mod app;
 mod app_modes;
+mod bag;
 mod config;
 mod footprint;
+mod health;
 mod image;
 mod laser;
 mod listeners;
@@ -9,7 +11,13 @@ mod map;
 mod marker;
 mod pointcloud;
 mod pose;
+mod record;
+#[cfg(test)]
+mod snapshot_tests;
+mod tf;
+mod trail;
 mod transformation;
+mod transport;
 use futures::{future::FutureExt, select, StreamExt};
 use futures_timer::Delay;
 use std::collections::HashMap;
@@ -23,8 +31,9 @@ use crossterm::{
     terminal::{disable_raw_mode, LeaveAlternateScreen},
 };
 use rosrust;
-use rustros_tf::TfListener;
 use std::error::Error;
+use std::path::Path;
+use transport::{MockTransport, RosTransport, Transport};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -35,13 +44,31 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .arg(
             Arg::new("config").long_help("Optional YAML file with a custom termviz configuration."),
         )
+        .arg(
+            Arg::new("bag")
+                .long("bag")
+                .value_name("FILE")
+                .help("Plays a ROS1 bag file instead of connecting to ROS."),
+        )
         .after_help("More documentation can be found at: https://github.com/carzum/termviz")
         .get_matches();
 
-    let conf = config::get_config(matches.get_one("config"))?;
-
-    println!("Connecting to ros...");
-    rosrust::init("termviz");
+    let (conf, config_path) = config::get_config(matches.get_one("config"))?;
+
+    let bag_path: Option<&String> = matches.get_one("bag");
+    let (transport, player): (Arc<dyn Transport>, Option<bag::BagPlayer>) = match bag_path {
+        Some(path) => {
+            println!("Reading bag {}...", path);
+            let mock = MockTransport::new();
+            let player = bag::BagPlayer::open(Path::new(path), mock.clone(), false)?;
+            (Arc::new(mock), Some(player))
+        }
+        None => {
+            println!("Connecting to ros...");
+            rosrust::init("termviz");
+            (Arc::new(RosTransport::new()), None)
+        }
+    };
 
     let mut key_to_input: HashMap<KeyCode, String> = conf
         .key_mapping
@@ -64,13 +91,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
         "Waiting for tf from {:?} to {:?} to become available...",
         conf.fixed_frame, conf.robot_frame
     );
-    let listener = Arc::new(TfListener::new());
-    while rosrust::is_ok() {
+    loop {
         let res =
-            listener.lookup_transform(&conf.fixed_frame, &conf.robot_frame, rosrust::Time::new());
+            transport.lookup_transform(&conf.fixed_frame, &conf.robot_frame, rosrust::Time::new());
         match res {
             Ok(_res) => break,
-            Err(_e) => {
+            Err(e) => {
+                let stopped = match &player {
+                    Some(player) => player.is_finished(),
+                    None => !rosrust::is_ok(),
+                };
+                if stopped {
+                    return Err(e.0.into());
+                }
                 std::thread::sleep(std::time::Duration::from_millis(100));
                 continue;
             }
@@ -81,7 +114,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let rate = Duration::from_millis(1000 / conf.target_framerate as u64);
 
-    let default_app_config = Arc::new(Mutex::new(app::App::new(listener.clone(), conf)));
+    let default_app_config = Arc::new(Mutex::new(app::App::new(
+        transport,
+        conf,
+        config_path,
+        player.clone(),
+    )));
 
     let mut running_app = default_app_config.lock().unwrap();
 
@@ -129,5 +167,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         DisableMouseCapture
     )?;
     terminal.show_cursor()?;
+    if let Some(player) = player {
+        player.stop();
+    }
     Ok(())
 }
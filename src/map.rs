@@ -1,87 +1,228 @@
-use crate::config::MapListenerConfig;
+use crate::config::{Color, MapListenerConfig};
+use crate::health::TopicHealth;
 use crate::transformation;
+use crate::transport::{Subscription, Transport};
+use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
-use nalgebra::geometry::{Isometry3, Point3, Quaternion, Translation3, UnitQuaternion};
+use nalgebra::geometry::{Isometry3, Point3};
 
-use rosrust;
-use rustros_tf;
+/// Occupancy grid, with the pose of its origin in the static frame.
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    pub resolution: f64,
+    pub pose: Isometry3<f64>,
+    pub data: Vec<i8>,
+}
+
+impl Grid {
+    /// Replaces the cells covered by an update patch, ignoring the ones outside the grid.
+    /// Malformed patches, whose data does not match their size, are ignored.
+    fn apply_update(&mut self, update: &rosrust_msg::map_msgs::OccupancyGridUpdate) {
+        let patch_width = update.width as usize;
+        if patch_width == 0 || update.data.len() != patch_width * update.height as usize {
+            return;
+        }
+        for (i, value) in update.data.iter().enumerate() {
+            let column = update.x as i64 + (i % patch_width) as i64;
+            let line = update.y as i64 + (i / patch_width) as i64;
+            if column < 0 || line < 0 || column >= self.width as i64 || line >= self.height as i64 {
+                continue;
+            }
+            self.data[line as usize * self.width + column as usize] = *value;
+        }
+    }
+
+    /// Returns the value of the cell containing the point, if it is inside the grid.
+    fn cell_at(&self, inverse_pose: &Isometry3<f64>, x: f64, y: f64) -> Option<i8> {
+        let pt = inverse_pose.transform_point(&Point3::new(x, y, 0.));
+        let column = (pt.x / self.resolution).floor();
+        let line = (pt.y / self.resolution).floor();
+        if column < 0. || line < 0. || column >= self.width as f64 || line >= self.height as f64 {
+            return None;
+        }
+        self.data
+            .get(line as usize * self.width + column as usize)
+            .copied()
+    }
+}
+
+/// Points of the map sharing the same color.
+pub struct ColoredPoints {
+    pub color: tui::style::Color,
+    pub points: Vec<(f64, f64)>,
+}
+
+fn to_tui_color(color: &Color) -> tui::style::Color {
+    tui::style::Color::Rgb(color.r, color.g, color.b)
+}
+
+fn blend(from: &Color, to: &Color, ratio: f64) -> tui::style::Color {
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * ratio).round() as u8;
+    tui::style::Color::Rgb(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b))
+}
 
 pub struct MapListener {
     pub config: MapListenerConfig,
-    pub points: Arc<RwLock<Vec<(f64, f64)>>>,
-    _tf_listener: Arc<rustros_tf::TfListener>,
+    pub grid: Arc<RwLock<Option<Grid>>>,
+    pub health: TopicHealth,
+    _transport: Arc<dyn Transport>,
     _static_frame: String,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: Subscription,
+    _update_subscriber: Option<Subscription>,
 }
 
 impl MapListener {
     pub fn new(
         config: MapListenerConfig,
-        tf_listener: Arc<rustros_tf::TfListener>,
+        transport: Arc<dyn Transport>,
         static_frame: String,
     ) -> MapListener {
-        let occ_points = Arc::new(RwLock::new(Vec::<(f64, f64)>::new()));
-        let cb_occ_points = occ_points.clone();
+        let grid = Arc::new(RwLock::new(None));
+        let cb_grid = grid.clone();
+        let health = TopicHealth::new(true);
+        let cb_health = health.clone();
         let str_ = static_frame.clone();
-        let local_listener = tf_listener.clone();
-        let threshold = config.threshold.clone();
-        let _map_sub = rosrust::subscribe(
-            &config.topic,
-            1,
-            move |map: rosrust_msg::nav_msgs::OccupancyGrid| {
-                let mut points: Vec<(f64, f64)> = Vec::new();
-                let res = local_listener.clone().lookup_transform(
-                    &str_,
-                    &map.header.frame_id,
-                    map.header.stamp,
-                );
-                match &res {
-                    Ok(res) => res,
-                    Err(_e) => return,
-                };
-
-                let tra = Translation3::new(
-                    map.info.origin.position.x,
-                    map.info.origin.position.y,
-                    map.info.origin.position.z,
-                );
-                let rot = UnitQuaternion::new_normalize(Quaternion::new(
-                    map.info.origin.orientation.w,
-                    map.info.origin.orientation.x,
-                    map.info.origin.orientation.y,
-                    map.info.origin.orientation.z,
-                ));
-                let isometry = Isometry3::from_parts(tra, rot);
+        let local_transport = transport.clone();
+        let _map_sub = transport
+            .subscribe(
+                &config.topic,
+                1,
+                move |map: rosrust_msg::nav_msgs::OccupancyGrid| {
+                    cb_health.record(Some(map.header.stamp));
+                    let res = local_transport.clone().lookup_transform(
+                        &str_,
+                        &map.header.frame_id,
+                        map.header.stamp,
+                    );
+                    let tf = match &res {
+                        Ok(res) => res,
+                        Err(_e) => return,
+                    };
+                    let pose = transformation::ros_transform_to_isometry(&tf.transform)
+                        * transformation::ros_pose_to_isometry(&map.info.origin);
+                    *cb_grid.write().unwrap() = Some(Grid {
+                        width: map.info.width as usize,
+                        height: map.info.height as usize,
+                        resolution: map.info.resolution as f64,
+                        pose: pose,
+                        data: map.data,
+                    });
+                },
+            )
+            .unwrap();
 
-                for (i, pt) in map.data.iter().enumerate() {
-                    let line = i / map.info.width as usize;
-                    let column = i - line * map.info.width as usize;
-                    if pt >= &threshold {
-                        let trans_point = isometry.transform_point(&Point3::new(
-                            (column as f64) * map.info.resolution as f64,
-                            line as f64 * map.info.resolution as f64,
-                            0.,
-                        ));
-                        let global_point = transformation::transform_relative_pt(
-                            &res.as_ref().unwrap().transform,
-                            (trans_point[0], trans_point[1]),
-                        );
-                        points.push(global_point);
-                    }
-                }
-                let mut cb_occ_points = cb_occ_points.write().unwrap();
-                *cb_occ_points = points;
-            },
-        )
-        .unwrap();
+        let _update_sub = if config.subscribe_updates {
+            let cb_grid = grid.clone();
+            Some(
+                transport
+                    .subscribe(
+                        &format!("{}_updates", config.topic),
+                        10,
+                        move |update: rosrust_msg::map_msgs::OccupancyGridUpdate| {
+                            // patches received before the full grid are dropped
+                            if let Some(grid) = cb_grid.write().unwrap().as_mut() {
+                                grid.apply_update(&update);
+                            }
+                        },
+                    )
+                    .unwrap(),
+            )
+        } else {
+            None
+        };
 
         MapListener {
             config,
-            points: occ_points,
-            _tf_listener: tf_listener,
+            grid: grid,
+            health: health,
+            _transport: transport,
             _static_frame: static_frame.to_string(),
             _subscriber: _map_sub,
+            _update_subscriber: _update_sub,
+        }
+    }
+
+    /// Returns the layer and the color of a cell value, or None if it is not drawn.
+    ///
+    /// Layers are drawn in order, so that occupied cells are on top of free and unknown ones.
+    fn cell_style(&self, value: i8) -> Option<(usize, tui::style::Color)> {
+        let config = &self.config;
+        if value < 0 {
+            return config.unknown_color.as_ref().map(|c| (0, to_tui_color(c)));
+        }
+        if config.color_scheme == "costmap" {
+            return match value {
+                0 => config.free_color.as_ref().map(|c| (1, to_tui_color(c))),
+                99 => Some((2, tui::style::Color::Cyan)),
+                100 => Some((2, tui::style::Color::Magenta)),
+                _ => Some((
+                    2,
+                    blend(
+                        &Color { r: 0, g: 0, b: 255 },
+                        &Color { r: 255, g: 0, b: 0 },
+                        (value.min(98) - 1) as f64 / 97.,
+                    ),
+                )),
+            };
+        }
+        if value < config.threshold {
+            return config.free_color.as_ref().map(|c| (1, to_tui_color(c)));
+        }
+        // occupied cells are shaded by their probability
+        let free_color = config
+            .free_color
+            .clone()
+            .unwrap_or(Color { r: 0, g: 0, b: 0 });
+        Some((
+            2,
+            blend(&free_color, &config.color, value.min(100) as f64 / 100.),
+        ))
+    }
+
+    /// Samples the visible part of the map on a regular grid of points, e.g. one per dot of the
+    /// canvas, so that the cost of drawing does not depend on the size of the map.
+    ///
+    /// Returns the sampled points by layer, from bottom to top, grouped by color.
+    pub fn sample(
+        &self,
+        x_bounds: [f64; 2],
+        y_bounds: [f64; 2],
+        samples: (usize, usize),
+    ) -> Vec<Vec<ColoredPoints>> {
+        let mut layers: Vec<Vec<ColoredPoints>> = vec![Vec::new(), Vec::new(), Vec::new()];
+        let grid_guard = self.grid.read().unwrap();
+        let grid = match &*grid_guard {
+            Some(grid) => grid,
+            None => return layers,
+        };
+        let inverse_pose = grid.pose.inverse();
+        let step_x = (x_bounds[1] - x_bounds[0]) / samples.0.max(1) as f64;
+        let step_y = (y_bounds[1] - y_bounds[0]) / samples.1.max(1) as f64;
+        let mut points_by_value: HashMap<i8, Vec<(f64, f64)>> = HashMap::new();
+        for i in 0..samples.0 {
+            let x = x_bounds[0] + (i as f64 + 0.5) * step_x;
+            for j in 0..samples.1 {
+                let y = y_bounds[0] + (j as f64 + 0.5) * step_y;
+                if let Some(value) = grid.cell_at(&inverse_pose, x, y) {
+                    points_by_value
+                        .entry(value)
+                        .or_insert_with(Vec::new)
+                        .push((x, y));
+                }
+            }
+        }
+        let mut values: Vec<i8> = points_by_value.keys().copied().collect();
+        values.sort();
+        for value in values {
+            if let Some((layer, color)) = self.cell_style(value) {
+                layers[layer].push(ColoredPoints {
+                    color: color,
+                    points: points_by_value.remove(&value).unwrap(),
+                });
+            }
         }
+        layers
     }
 }
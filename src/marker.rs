@@ -4,43 +4,107 @@
 //! This module allows to subsribe to topics that publish them and project them into the
 //! 2D plane. Finally, it takes care of their lifecycle: ADD, DELETE and timeout.
 use crate::config::ListenerConfig;
+use crate::health::TopicHealth;
+use crate::transport::{MessageDescription, Subscription, Transport};
 use nalgebra::base::Vector3;
 use nalgebra::geometry::Isometry3;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::f64::consts::PI;
 use std::sync::{Arc, Mutex, RwLock};
 
-use rosrust;
 use rustros_tf::transforms::nalgebra::geometry::Point3;
 use rustros_tf::transforms::{isometry_from_pose, isometry_from_transform};
 
 use tui::style::Color;
 use tui::widgets::canvas::Line;
 
+/// Text drawn at a position of the static frame.
+#[derive(Clone)]
+pub struct Label {
+    pub x: f64,
+    pub y: f64,
+    pub text: String,
+    pub color: Color,
+}
+
 struct TermvizMarker {
     pub lines: Vec<Line>,
+    pub labels: Vec<Label>,
     pub id: i32,
+    /// Message of a frame locked marker, kept in its own frame to be projected again with
+    /// the latest transform of the frame.
+    pub frame_locked_msg: Option<rosrust_msg::visualization_msgs::Marker>,
+}
+
+/// Number of segments used to approximate ellipses.
+const ELLIPSE_SEGMENTS: usize = 24;
+
+/// Alpha under which a vertex color is considered transparent.
+const MIN_ALPHA: f32 = 0.1;
+
+/// Converts a ROS color to a terminal color, dimmed by its alpha.
+/// Returns None if the color is almost transparent, i.e. should not be drawn.
+fn to_dimmed_color(color: &rosrust_msg::std_msgs::ColorRGBA) -> Option<Color> {
+    if color.a < MIN_ALPHA {
+        return None;
+    }
+    let alpha = color.a.min(1.0);
+    Some(Color::Rgb(
+        (color.r * alpha * 255.0) as u8,
+        (color.g * alpha * 255.0) as u8,
+        (color.b * alpha * 255.0) as u8,
+    ))
+}
+
+/// Returns the colors of the points of the marker, if it provides one color per point and its
+/// type uses them.
+fn vertex_colors(msg: &rosrust_msg::visualization_msgs::Marker) -> Option<Vec<Option<Color>>> {
+    let uses_vertex_colors = matches!(
+        msg.type_ as u8,
+        rosrust_msg::visualization_msgs::Marker::LINE_STRIP
+            | rosrust_msg::visualization_msgs::Marker::LINE_LIST
+            | rosrust_msg::visualization_msgs::Marker::POINTS
+            | rosrust_msg::visualization_msgs::Marker::CUBE_LIST
+            | rosrust_msg::visualization_msgs::Marker::SPHERE_LIST
+    );
+    if !uses_vertex_colors || msg.colors.is_empty() || msg.colors.len() != msg.points.len() {
+        return None;
+    }
+    Some(msg.colors.iter().map(to_dimmed_color).collect())
 }
 
 /// Creates a list of lines from N line strips.
 /// # Arguments
 /// - `strips`: A vector of vector of points. Each element is a strip, i.e. a single
 ///             broken line. Each strip has N points that form N-1 lines.
-fn from_point_strips(strips: &Vec<Vec<Point3<f64>>>, color: &Color) -> Vec<Line> {
+/// - `color`: Color of the lines.
+/// - `vertex_colors`: Optional colors of the points of each strip, overriding `color`. Each
+///                    line takes the color of its first point, and is skipped if it is None.
+fn from_point_strips(
+    strips: &Vec<Vec<Point3<f64>>>,
+    color: &Color,
+    vertex_colors: Option<&Vec<Vec<Option<Color>>>>,
+) -> Vec<Line> {
     let mut lines: Vec<Line> = Vec::new();
 
-    for strip in strips {
+    for (i, strip) in strips.iter().enumerate() {
         let mut previous_point: Option<&Point3<f64>> = None;
-        for point in strip {
+        for (j, point) in strip.iter().enumerate() {
             if previous_point.is_some() {
+                let line_color = match vertex_colors {
+                    Some(colors) => colors[i][j - 1],
+                    None => Some(*color),
+                };
                 let pp = previous_point.unwrap();
-                lines.push(Line {
-                    x1: pp.x,
-                    y1: pp.y,
-                    x2: point.x,
-                    y2: point.y,
-                    color: *color,
-                });
+                if let Some(line_color) = line_color {
+                    lines.push(Line {
+                        x1: pp.x,
+                        y1: pp.y,
+                        x2: point.x,
+                        y2: point.y,
+                        color: line_color,
+                    });
+                }
             }
             previous_point = Some(point);
         }
@@ -64,8 +128,6 @@ fn parse_cube(
     color: &tui::style::Color,
     iso: &Isometry3<f64>,
 ) -> Vec<Line> {
-    let angles = iso.rotation.euler_angles();
-
     let mut points_strips: Vec<Vec<Point3<f64>>> = Vec::new();
 
     let face_top = vec![
@@ -97,7 +159,7 @@ fn parse_cube(
     ];
     points_strips.push(face_top);
 
-    if angles.0.abs() > 0.0001 || angles.1.abs() > 0.0001 {
+    if is_tilted(iso) {
         // Other faces may be visible, render all of them
         let face_bottom = vec![
             iso.transform_point(&Point3::new(
@@ -177,7 +239,144 @@ fn parse_cube(
         points_strips.extend([face_bottom, a, b, c, d]);
     }
 
-    return from_point_strips(&points_strips, color);
+    return from_point_strips(&points_strips, color, None);
+}
+
+/// Creates the closed strip of an ellipse in the XY plane of the iso transformation.
+/// # Arguments:
+/// - `dimension`: diameters of the ellipse along x and y.
+/// - `offset`: Offset of the center of the ellipse in the iso transformation.
+/// - `z`: Height of the plane of the ellipse relative to its center.
+/// - `iso`: Base transformation of the ellipse.
+fn ellipse_strip(
+    dimension: &rosrust_msg::geometry_msgs::Vector3,
+    offset: &rosrust_msg::geometry_msgs::Point,
+    z: f64,
+    iso: &Isometry3<f64>,
+) -> Vec<Point3<f64>> {
+    (0..=ELLIPSE_SEGMENTS)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / ELLIPSE_SEGMENTS as f64;
+            iso.transform_point(&Point3::new(
+                offset.x + dimension.x / 2.0 * angle.cos(),
+                offset.y + dimension.y / 2.0 * angle.sin(),
+                offset.z + z,
+            ))
+        })
+        .collect()
+}
+
+fn is_tilted(iso: &Isometry3<f64>) -> bool {
+    let angles = iso.rotation.euler_angles();
+    angles.0.abs() > 0.0001 || angles.1.abs() > 0.0001
+}
+
+/// Creates the outline of a sphere, i.e. an ellipse with its diameters.
+fn parse_sphere(
+    dimension: &rosrust_msg::geometry_msgs::Vector3,
+    offset: &rosrust_msg::geometry_msgs::Point,
+    color: &tui::style::Color,
+    iso: &Isometry3<f64>,
+) -> Vec<Line> {
+    from_point_strips(
+        &vec![ellipse_strip(dimension, offset, 0.0, iso)],
+        color,
+        None,
+    )
+}
+
+fn origin() -> rosrust_msg::geometry_msgs::Point {
+    rosrust_msg::geometry_msgs::Point {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    }
+}
+
+fn parse_sphere_msg(
+    msg: &rosrust_msg::visualization_msgs::Marker,
+    color: &tui::style::Color,
+    iso: &Isometry3<f64>,
+) -> Vec<Line> {
+    parse_sphere(&msg.scale, &origin(), color, iso)
+}
+
+fn parse_sphere_list_msg(
+    msg: &rosrust_msg::visualization_msgs::Marker,
+    color: &tui::style::Color,
+    iso: &Isometry3<f64>,
+) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let colors = vertex_colors(msg);
+
+    for (i, point) in msg.points.iter().enumerate() {
+        let point_color = match &colors {
+            Some(colors) => colors[i],
+            None => Some(*color),
+        };
+        if let Some(point_color) = point_color {
+            lines.extend(parse_sphere(&msg.scale, &point, &point_color, iso));
+        }
+    }
+
+    lines
+}
+
+/// Creates the visible lines of a cylinder along the z axis.
+///
+/// If the cylinder is parallel to the plan, only its top cap is visible.
+/// Else, both caps are drawn, linked along the x and y axes.
+fn parse_cylinder_msg(
+    msg: &rosrust_msg::visualization_msgs::Marker,
+    color: &tui::style::Color,
+    iso: &Isometry3<f64>,
+) -> Vec<Line> {
+    let top = ellipse_strip(&msg.scale, &origin(), msg.scale.z / 2.0, iso);
+    if !is_tilted(iso) {
+        return from_point_strips(&vec![top], color, None);
+    }
+    let bottom = ellipse_strip(&msg.scale, &origin(), -msg.scale.z / 2.0, iso);
+    let mut strips = Vec::new();
+    for i in (0..ELLIPSE_SEGMENTS).step_by(ELLIPSE_SEGMENTS / 4) {
+        strips.push(vec![top[i], bottom[i]]);
+    }
+    strips.push(top);
+    strips.push(bottom);
+    from_point_strips(&strips, color, None)
+}
+
+fn parse_triangle_list_msg(
+    msg: &rosrust_msg::visualization_msgs::Marker,
+    color: &tui::style::Color,
+    iso: &Isometry3<f64>,
+) -> Vec<Line> {
+    let strips: Vec<Vec<Point3<f64>>> = msg
+        .points
+        .chunks_exact(3)
+        .map(|triangle| {
+            let mut strip: Vec<Point3<f64>> = triangle
+                .iter()
+                .map(|p| iso.transform_point(&Point3::new(p.x, p.y, p.z)))
+                .collect();
+            strip.push(strip[0]);
+            strip
+        })
+        .collect();
+    from_point_strips(&strips, color, None)
+}
+
+fn parse_text_msg(
+    msg: &rosrust_msg::visualization_msgs::Marker,
+    color: &tui::style::Color,
+    iso: &Isometry3<f64>,
+) -> Vec<Label> {
+    let position = iso.transform_point(&Point3::new(0.0, 0.0, 0.0));
+    vec![Label {
+        x: position.x,
+        y: position.y,
+        text: msg.text.clone(),
+        color: *color,
+    }]
 }
 
 fn parse_arrow_msg(
@@ -282,16 +481,7 @@ fn parse_cube_msg(
 ) -> Vec<Line> {
     let center_offset_msg = msg.points.get(0);
     if center_offset_msg.is_none() {
-        return parse_cube(
-            &msg.scale,
-            &rosrust_msg::geometry_msgs::Point {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
-            color,
-            iso,
-        );
+        return parse_cube(&msg.scale, &origin(), color, iso);
     }
     return parse_cube(&msg.scale, &center_offset_msg.unwrap(), color, iso);
 }
@@ -302,9 +492,16 @@ fn parse_cube_list_msg(
     iso: &Isometry3<f64>,
 ) -> Vec<Line> {
     let mut lines = Vec::new();
+    let colors = vertex_colors(msg);
 
-    for point in msg.points.iter() {
-        lines.extend(parse_cube(&msg.scale, &point, color, iso));
+    for (i, point) in msg.points.iter().enumerate() {
+        let point_color = match &colors {
+            Some(colors) => colors[i],
+            None => Some(*color),
+        };
+        if let Some(point_color) = point_color {
+            lines.extend(parse_cube(&msg.scale, &point, &point_color, iso));
+        }
     }
 
     lines
@@ -329,7 +526,8 @@ fn parse_line_strip_msg(
         points.push(iso.transform_point(&Point3::new(point.x, point.y, point.z)));
     }
 
-    return from_point_strips(&vec![points], color);
+    let colors = vertex_colors(msg).map(|colors| vec![colors]);
+    return from_point_strips(&vec![points], color, colors.as_ref());
 }
 
 fn parse_line_list_msg(
@@ -338,21 +536,29 @@ fn parse_line_list_msg(
     iso: &Isometry3<f64>,
 ) -> Vec<Line> {
     let mut lines: Vec<Line> = Vec::new();
+    let colors = vertex_colors(msg);
 
-    let mut point_it = msg.points.iter();
+    let mut point_it = msg.points.iter().enumerate();
 
-    while let Some(msg_p1) = point_it.next() {
+    while let Some((i, msg_p1)) = point_it.next() {
         let p1 = iso.transform_point(&Point3::new(msg_p1.x, msg_p1.y, msg_p1.z));
-        let msg_p2 = point_it.next().expect("Malformed message.");
+        let (_, msg_p2) = point_it.next().expect("Malformed message.");
         let p2 = iso.transform_point(&Point3::new(msg_p2.x, msg_p2.y, msg_p2.z));
 
-        lines.push(Line {
-            x1: p1.x,
-            y1: p1.y,
-            x2: p2.x,
-            y2: p2.y,
-            color: *color,
-        });
+        // the line takes the color of its first point
+        let line_color = match &colors {
+            Some(colors) => colors[i],
+            None => Some(*color),
+        };
+        if let Some(line_color) = line_color {
+            lines.push(Line {
+                x1: p1.x,
+                y1: p1.y,
+                x2: p2.x,
+                y2: p2.y,
+                color: line_color,
+            });
+        }
     }
     lines
 }
@@ -366,11 +572,19 @@ fn parse_marker_msg(
 
     let iso = trans_marker_to_static_frame.inverse() * trans_to_marker;
 
-    let color = Color::Rgb(
-        (msg.color.r * 255.0) as u8,
-        (msg.color.g * 255.0) as u8,
-        (msg.color.b * 255.0) as u8,
-    );
+    // a transparent marker is only drawn with the colors of its points, if it has some
+    let color = match to_dimmed_color(&msg.color) {
+        Some(color) => color,
+        None if vertex_colors(msg).is_some() => Color::Reset,
+        None => {
+            return TermvizMarker {
+                lines: Vec::new(),
+                labels: Vec::new(),
+                id: msg.id,
+                frame_locked_msg: None,
+            }
+        }
+    };
 
     let res = match msg.type_ as u8 {
         rosrust_msg::visualization_msgs::Marker::ARROW => parse_arrow_msg(msg, &color, &iso),
@@ -385,12 +599,37 @@ fn parse_marker_msg(
         rosrust_msg::visualization_msgs::Marker::LINE_LIST => {
             parse_line_list_msg(msg, &color, &iso)
         }
+        rosrust_msg::visualization_msgs::Marker::SPHERE => parse_sphere_msg(msg, &color, &iso),
+        rosrust_msg::visualization_msgs::Marker::SPHERE_LIST => {
+            parse_sphere_list_msg(msg, &color, &iso)
+        }
+        rosrust_msg::visualization_msgs::Marker::CYLINDER => parse_cylinder_msg(msg, &color, &iso),
+        rosrust_msg::visualization_msgs::Marker::TRIANGLE_LIST => {
+            parse_triangle_list_msg(msg, &color, &iso)
+        }
+        // meshes are not loaded, their bounding box is approximated by the scale
+        rosrust_msg::visualization_msgs::Marker::MESH_RESOURCE => {
+            parse_cube(&msg.scale, &origin(), &color, &iso)
+        }
+        _ => Vec::new(),
+    };
+
+    let labels = match msg.type_ as u8 {
+        rosrust_msg::visualization_msgs::Marker::TEXT_VIEW_FACING => {
+            parse_text_msg(msg, &color, &iso)
+        }
         _ => Vec::new(),
     };
 
     TermvizMarker {
         lines: res,
+        labels: labels,
         id: msg.id,
+        frame_locked_msg: if msg.frame_locked {
+            Some(msg.clone())
+        } else {
+            None
+        },
     }
 }
 
@@ -402,27 +641,45 @@ fn parse_marker_msg(
 /// publishers such that they can be managed globally.
 struct TermvizMarkerContainer {
     markers: HashMap<String, HashMap<i32, TermvizMarker>>,
+    /// Topic on which the markers of each namespace were last received.
+    namespace_topics: HashMap<String, String>,
+    hidden_namespaces: HashSet<String>,
     static_frame: String,
-    tf_listener: Arc<rustros_tf::TfListener>,
+    transport: Arc<dyn Transport>,
+}
+
+/// Summary of a namespace of markers.
+pub struct MarkerNamespace {
+    pub name: String,
+    pub topic: String,
+    pub count: usize,
+    pub hidden: bool,
 }
 
 impl TermvizMarkerContainer {
-    pub fn new(
-        tf_listener: Arc<rustros_tf::TfListener>,
-        static_frame: String,
-    ) -> TermvizMarkerContainer {
+    pub fn new(transport: Arc<dyn Transport>, static_frame: String) -> TermvizMarkerContainer {
         Self {
             markers: HashMap::<String, HashMap<i32, TermvizMarker>>::new(),
+            namespace_topics: HashMap::new(),
+            hidden_namespaces: HashSet::new(),
             static_frame: static_frame,
-            tf_listener: tf_listener,
+            transport: transport,
         }
     }
 
-    fn add_marker(&mut self, marker: &rosrust_msg::visualization_msgs::Marker) {
-        let transform = &self.tf_listener.clone().lookup_transform(
+    fn add_marker(&mut self, marker: &rosrust_msg::visualization_msgs::Marker, topic: &str) {
+        self.namespace_topics
+            .insert(marker.ns.clone(), topic.to_string());
+        // frame locked markers follow the latest transform of their frame
+        let stamp = if marker.frame_locked {
+            rosrust::Time::new()
+        } else {
+            marker.header.stamp
+        };
+        let transform = &self.transport.clone().lookup_transform(
             &marker.header.frame_id,
             &self.static_frame.clone(),
-            marker.header.stamp,
+            stamp,
         );
         match &transform {
             Ok(transform) => transform,
@@ -464,15 +721,93 @@ impl TermvizMarkerContainer {
         res
     }
 
-    fn get_lines(&self) -> Vec<Line> {
+    /// Projects the frame locked markers with the latest transforms of their frames.
+    /// Markers whose transform is not available keep their previous projection.
+    fn update_frame_locked(&mut self) {
+        for namespace in self.markers.values_mut() {
+            for marker in namespace.values_mut() {
+                let msg = match &marker.frame_locked_msg {
+                    Some(msg) => msg,
+                    None => continue,
+                };
+                let transform = self.transport.lookup_transform(
+                    &msg.header.frame_id,
+                    &self.static_frame,
+                    rosrust::Time::new(),
+                );
+                if let Ok(transform) = transform {
+                    let projected = parse_marker_msg(msg, &transform.transform);
+                    marker.lines = projected.lines;
+                    marker.labels = projected.labels;
+                }
+            }
+        }
+    }
+
+    fn get_lines(&mut self) -> Vec<Line> {
+        self.update_frame_locked();
         let mut res = Vec::<Line>::new();
-        for namespace in self.markers.values() {
+        for (ns, namespace) in self.markers.iter() {
+            if self.hidden_namespaces.contains(ns) {
+                continue;
+            }
             for marker in namespace.values() {
                 res.extend(marker.lines.to_vec());
             }
         }
         res
     }
+
+    fn get_labels(&self) -> Vec<Label> {
+        let mut res = Vec::<Label>::new();
+        for (ns, namespace) in self.markers.iter() {
+            if self.hidden_namespaces.contains(ns) {
+                continue;
+            }
+            for marker in namespace.values() {
+                res.extend(marker.labels.to_vec());
+            }
+        }
+        res
+    }
+
+    fn namespaces(&self) -> Vec<MarkerNamespace> {
+        let mut res: Vec<MarkerNamespace> = self
+            .markers
+            .iter()
+            .map(|(ns, namespace)| MarkerNamespace {
+                name: ns.clone(),
+                topic: self.namespace_topics.get(ns).cloned().unwrap_or_default(),
+                count: namespace.len(),
+                hidden: self.hidden_namespaces.contains(ns),
+            })
+            .collect();
+        res.sort_by(|a, b| a.name.cmp(&b.name));
+        res
+    }
+
+    fn set_namespace_hidden(&mut self, marker_ns: &str, hidden: bool) {
+        if hidden {
+            self.hidden_namespaces.insert(marker_ns.to_string());
+        } else {
+            self.hidden_namespaces.remove(marker_ns);
+        }
+    }
+
+    /// Removes the namespaces whose markers were received on the given topic.
+    fn remove_topic(&mut self, topic: &str) {
+        let namespaces: Vec<String> = self
+            .namespace_topics
+            .iter()
+            .filter(|(_, ns_topic)| ns_topic.as_str() == topic)
+            .map(|(ns, _)| ns.clone())
+            .collect();
+        for ns in namespaces {
+            self.markers.remove(&ns);
+            self.namespace_topics.remove(&ns);
+            self.hidden_namespaces.remove(&ns);
+        }
+    }
 }
 
 /// Class that handles the lifecycle of the markers.
@@ -525,8 +860,11 @@ impl MarkersLifecycle {
         }
     }
 
-    fn add_marker(&mut self, marker: &rosrust_msg::visualization_msgs::Marker) {
-        self.markers_container.write().unwrap().add_marker(marker);
+    fn add_marker(&mut self, marker: &rosrust_msg::visualization_msgs::Marker, topic: &str) {
+        self.markers_container
+            .write()
+            .unwrap()
+            .add_marker(marker, topic);
 
         // Handle marker lifecycle
         if marker.lifetime.seconds() == 0.0 {
@@ -587,19 +925,46 @@ impl MarkersLifecycle {
     fn get_lines(&self) -> Vec<Line> {
         self.markers_container.write().unwrap().get_lines()
     }
+
+    fn get_labels(&self) -> Vec<Label> {
+        self.markers_container.read().unwrap().get_labels()
+    }
+}
+
+fn handle_marker_msg(
+    markers_lifecycle: &Arc<RwLock<MarkersLifecycle>>,
+    msg: &rosrust_msg::visualization_msgs::Marker,
+    topic: &str,
+) {
+    let mut markers_container = markers_lifecycle.write().unwrap();
+
+    match msg.action as u8 {
+        rosrust_msg::visualization_msgs::Marker::ADD => markers_container.add_marker(msg, topic),
+        rosrust_msg::visualization_msgs::Marker::DELETE => {
+            markers_container.delete_marker(msg.ns.clone(), msg.id)
+        }
+        rosrust_msg::visualization_msgs::Marker::DELETEALL => markers_container.clear(),
+        _ => return,
+    }
 }
 
 pub struct MarkersListener {
     markers_lifecycle: Arc<RwLock<MarkersLifecycle>>,
-    subscribers: Vec<Arc<Mutex<rosrust::Subscriber>>>,
+    subscribers: Vec<Arc<Mutex<Subscription>>>,
+    topics: Vec<(String, MessageDescription)>,
+    healths: Vec<TopicHealth>,
+    transport: Arc<dyn Transport>,
 }
 
 impl MarkersListener {
-    pub fn new(tf_listener: Arc<rustros_tf::TfListener>, static_frame: String) -> MarkersListener {
-        let marker_container = TermvizMarkerContainer::new(tf_listener, static_frame);
+    pub fn new(transport: Arc<dyn Transport>, static_frame: String) -> MarkersListener {
+        let marker_container = TermvizMarkerContainer::new(transport.clone(), static_frame);
         Self {
             markers_lifecycle: Arc::new(RwLock::new(MarkersLifecycle::new(marker_container))),
             subscribers: Vec::new(),
+            topics: Vec::new(),
+            healths: Vec::new(),
+            transport: transport,
         }
     }
 
@@ -609,33 +974,53 @@ impl MarkersListener {
         markers_container_ref.get_lines()
     }
 
+    /// Gets all the text labels currently active, to render.
+    pub fn get_labels(&self) -> Vec<Label> {
+        let markers_container_ref = self.markers_lifecycle.read().unwrap();
+        markers_container_ref.get_labels()
+    }
+
+    /// Returns the subscribed topics, with the description of their messages.
+    pub fn topics(&self) -> Vec<(String, MessageDescription)> {
+        self.topics.clone()
+    }
+
+    /// Returns the subscribed topics, with the statistics of their messages.
+    pub fn healths(&self) -> Vec<(String, TopicHealth)> {
+        self.topics
+            .iter()
+            .map(|(topic, _)| topic.clone())
+            .zip(self.healths.iter().cloned())
+            .collect()
+    }
+
     /// Adds a subscriber for a marker topic.
     ///
     /// # Arguments
     /// - `config`: Configuration containing the topic name.
     pub fn add_marker_listener(&mut self, config: &ListenerConfig) {
+        self.hide_configured_namespaces(config);
         let markers_container_ref = self.markers_lifecycle.clone();
+        let topic = config.topic.clone();
+        // markers are often published once, like latched topics
+        let health = TopicHealth::new(true);
+        let cb_health = health.clone();
 
-        let sub = rosrust::subscribe(
+        let sub = self.transport.subscribe(
             &config.topic,
             2,
             move |msg: rosrust_msg::visualization_msgs::Marker| {
-                let mut markers_container = markers_container_ref.write().unwrap();
-
-                match msg.action as u8 {
-                    rosrust_msg::visualization_msgs::Marker::ADD => {
-                        markers_container.add_marker(&msg)
-                    }
-                    rosrust_msg::visualization_msgs::Marker::DELETE => {
-                        markers_container.delete_marker(msg.ns.clone(), msg.id)
-                    }
-                    rosrust_msg::visualization_msgs::Marker::DELETEALL => markers_container.clear(),
-                    _ => return,
-                }
+                cb_health.record(Some(msg.header.stamp));
+                handle_marker_msg(&markers_container_ref, &msg, &topic);
             },
         );
 
         self.subscribers.push(Arc::new(Mutex::new(sub.unwrap())));
+        self.healths.push(health);
+        self.topics.push((
+            config.topic.clone(),
+            MessageDescription::of::<rosrust_msg::visualization_msgs::Marker>(),
+        ));
     }
 
     /// Adds a subscriber for a marker array message topic.
@@ -643,18 +1028,24 @@ impl MarkersListener {
     /// # Arguments
     /// * `config` - Configuration containing the topic.
     pub fn add_marker_array_listener(&mut self, config: &ListenerConfig) {
+        self.hide_configured_namespaces(config);
         let markers_container_ref = self.markers_lifecycle.clone();
+        let topic = config.topic.clone();
+        // markers are often published once, like latched topics
+        let health = TopicHealth::new(true);
+        let cb_health = health.clone();
 
-        let sub = rosrust::subscribe(
+        let sub = self.transport.subscribe(
             &config.topic,
             2,
             move |msg: rosrust_msg::visualization_msgs::MarkerArray| {
+                cb_health.record(msg.markers.first().map(|m| m.header.stamp));
                 let mut markers_container = markers_container_ref.write().unwrap();
 
                 for marker in msg.markers {
                     match marker.action as u8 {
                         rosrust_msg::visualization_msgs::Marker::ADD => {
-                            markers_container.add_marker(&marker)
+                            markers_container.add_marker(&marker, &topic)
                         }
                         rosrust_msg::visualization_msgs::Marker::DELETE => {
                             markers_container.delete_marker(marker.ns.clone(), marker.id)
@@ -669,5 +1060,87 @@ impl MarkersListener {
         );
 
         self.subscribers.push(Arc::new(Mutex::new(sub.unwrap())));
+        self.healths.push(health);
+        self.topics.push((
+            config.topic.clone(),
+            MessageDescription::of::<rosrust_msg::visualization_msgs::MarkerArray>(),
+        ));
+    }
+
+    /// Removes the subscriber of a marker or marker array topic, and the markers received on it.
+    pub fn remove_listener(&mut self, topic: &str) {
+        if let Some(idx) = self.topics.iter().position(|(t, _)| t == topic) {
+            self.topics.remove(idx);
+            self.healths.remove(idx);
+            self.subscribers.remove(idx);
+        }
+        let lifecycle = self.markers_lifecycle.read().unwrap();
+        lifecycle
+            .markers_container
+            .write()
+            .unwrap()
+            .remove_topic(topic);
+    }
+
+    fn hide_configured_namespaces(&self, config: &ListenerConfig) {
+        let lifecycle = self.markers_lifecycle.read().unwrap();
+        let mut container = lifecycle.markers_container.write().unwrap();
+        for ns in &config.hidden_namespaces {
+            container
+                .namespace_topics
+                .insert(ns.clone(), config.topic.clone());
+            container.set_namespace_hidden(ns, true);
+        }
+    }
+
+    /// Returns the namespaces of the active markers, sorted by name.
+    pub fn namespaces(&self) -> Vec<MarkerNamespace> {
+        let lifecycle = self.markers_lifecycle.read().unwrap();
+        let namespaces = lifecycle.markers_container.read().unwrap().namespaces();
+        namespaces
+    }
+
+    /// Hides or shows the markers of a namespace.
+    pub fn set_namespace_hidden(&self, marker_ns: &str, hidden: bool) {
+        let lifecycle = self.markers_lifecycle.read().unwrap();
+        lifecycle
+            .markers_container
+            .write()
+            .unwrap()
+            .set_namespace_hidden(marker_ns, hidden);
+    }
+
+    /// Shows only the markers of a namespace, hiding all the other active namespaces.
+    pub fn solo_namespace(&self, marker_ns: &str) {
+        let lifecycle = self.markers_lifecycle.read().unwrap();
+        let mut container = lifecycle.markers_container.write().unwrap();
+        for namespace in container.namespaces() {
+            container.set_namespace_hidden(&namespace.name, namespace.name != marker_ns);
+        }
+    }
+
+    /// Shows the markers of all the namespaces.
+    pub fn show_all_namespaces(&self) {
+        let lifecycle = self.markers_lifecycle.read().unwrap();
+        lifecycle
+            .markers_container
+            .write()
+            .unwrap()
+            .hidden_namespaces
+            .clear();
+    }
+
+    /// Returns the hidden namespaces whose markers were received on the given topic.
+    pub fn hidden_namespaces(&self, topic: &str) -> Vec<String> {
+        let lifecycle = self.markers_lifecycle.read().unwrap();
+        let container = lifecycle.markers_container.read().unwrap();
+        let mut res: Vec<String> = container
+            .hidden_namespaces
+            .iter()
+            .filter(|ns| container.namespace_topics.get(*ns).map(|t| t.as_str()) == Some(topic))
+            .cloned()
+            .collect();
+        res.sort();
+        res
     }
 }
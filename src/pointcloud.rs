@@ -1,21 +1,40 @@
 use crate::config::PointCloud2ListenerConfig;
-use byteorder::{ByteOrder, LittleEndian};
+use crate::health::TopicHealth;
+use crate::trail::Trail;
+use crate::transport::{Subscription, Transport};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use colorgrad;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 
 use nalgebra::geometry::Point3;
+use rosrust_msg::sensor_msgs::{PointCloud2, PointField};
 use tui::style::Color;
 
 use crate::transformation::ros_transform_to_isometry;
-use rosrust;
-use rustros_tf;
 
 pub struct PointCloud2Listener {
     pub config: PointCloud2ListenerConfig,
     pub points: Arc<RwLock<Vec<ColoredPoint>>>,
-    _tf_listener: Arc<rustros_tf::TfListener>,
+    pub trail: Arc<RwLock<Trail<Vec<ColoredPoint>>>>,
+    /// Center and value of the cells of the height map styles, which keep no points.
+    pub cells: Arc<RwLock<Vec<((f64, f64), f64)>>>,
+    pub health: TopicHealth,
+    /// Config read by the subscriber for each message.
+    cb_config: Arc<RwLock<PointCloud2ListenerConfig>>,
+    /// Settings of the viewport read by the subscriber for each message.
+    cb_view: Arc<RwLock<ViewSettings>>,
+    _transport: Arc<dyn Transport>,
     _static_frame: String,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: Subscription,
+}
+
+/// Settings of the viewport which the subscriber needs to keep only the points that are drawn.
+#[derive(Clone, Copy, Default)]
+struct ViewSettings {
+    /// Size of a dot of the viewport.
+    dot_size: f64,
+    slice_offset: f64,
 }
 
 #[derive(Clone)]
@@ -33,122 +52,640 @@ impl ColoredPoint {
     }
 }
 
-pub fn get_channel_offset(name: &str, fields: &Vec<rosrust_msg::sensor_msgs::PointField>) -> u32 {
-    for field in fields {
-        if field.name == name {
-            return field.offset;
+/// Region of the fixed frame in which the points of a point cloud are drawn.
+pub struct Region {
+    min_z: Option<f64>,
+    max_z: Option<f64>,
+    crop_box: Option<[f64; 4]>,
+}
+
+impl Region {
+    /// Returns the region of the config, with its z slice raised by the offset.
+    pub fn new(config: &PointCloud2ListenerConfig, slice_offset: f64) -> Region {
+        Region {
+            min_z: config.min_z.map(|z| z + slice_offset),
+            max_z: config.max_z.map(|z| z + slice_offset),
+            crop_box: config.crop_box,
         }
     }
-    panic!("Could not find field {:}", name);
+
+    pub fn contains(&self, point: &Point3<f64>) -> bool {
+        self.min_z.map_or(true, |z| point.z >= z)
+            && self.max_z.map_or(true, |z| point.z <= z)
+            && self.crop_box.map_or(true, |[min_x, max_x, min_y, max_y]| {
+                point.x >= min_x && point.x <= max_x && point.y >= min_y && point.y <= max_y
+            })
+    }
+}
+
+/// Returns the size in bytes of a value of a PointField datatype, or None if it is unknown.
+fn datatype_size(datatype: u8) -> Option<usize> {
+    match datatype {
+        PointField::INT8 | PointField::UINT8 => Some(1),
+        PointField::INT16 | PointField::UINT16 => Some(2),
+        PointField::INT32 | PointField::UINT32 | PointField::FLOAT32 => Some(4),
+        PointField::FLOAT64 => Some(8),
+        _ => None,
+    }
+}
+
+fn read_value<E: ByteOrder>(datatype: u8, bytes: &[u8]) -> f64 {
+    match datatype {
+        PointField::INT8 => bytes[0] as i8 as f64,
+        PointField::UINT8 => bytes[0] as f64,
+        PointField::INT16 => E::read_i16(bytes) as f64,
+        PointField::UINT16 => E::read_u16(bytes) as f64,
+        PointField::INT32 => E::read_i32(bytes) as f64,
+        PointField::UINT32 => E::read_u32(bytes) as f64,
+        PointField::FLOAT32 => E::read_f32(bytes) as f64,
+        _ => E::read_f64(bytes),
+    }
 }
 
-pub fn read_f32(bytes: &Vec<u8>, idx: u32) -> f32 {
-    LittleEndian::read_f32(&bytes[idx as usize..(idx + 4) as usize])
+/// Reader of a field of the points of a PointCloud2 message.
+pub struct FieldReader {
+    offset: usize,
+    datatype: u8,
+    size: usize,
+    is_bigendian: bool,
+}
+
+impl FieldReader {
+    /// Creates the reader of the field with the given name, or returns an error if the cloud has
+    /// no such field or if the field does not fit in the points.
+    pub fn new(name: &str, msg: &PointCloud2) -> Result<FieldReader, String> {
+        let field = msg
+            .fields
+            .iter()
+            .find(|f| f.name == name)
+            .ok_or_else(|| format!("Missing field {}", name))?;
+        let size = datatype_size(field.datatype)
+            .ok_or_else(|| format!("Unsupported datatype {} of field {}", field.datatype, name))?;
+        // a count of 0 is used by some publishers for a single value
+        let count = field.count.max(1) as usize;
+        if field.offset as usize + size * count > msg.point_step as usize {
+            return Err(format!(
+                "Field {} does not fit in the point step {}",
+                name, msg.point_step
+            ));
+        }
+        Ok(FieldReader {
+            offset: field.offset as usize,
+            datatype: field.datatype,
+            size: size,
+            is_bigendian: msg.is_bigendian,
+        })
+    }
+
+    fn bytes<'a>(&self, data: &'a [u8], point_start: usize) -> &'a [u8] {
+        &data[point_start + self.offset..point_start + self.offset + self.size]
+    }
+
+    /// Reads the first value of the field of the point starting at the given byte index.
+    pub fn read(&self, data: &[u8], point_start: usize) -> f64 {
+        let bytes = self.bytes(data, point_start);
+        if self.is_bigendian {
+            read_value::<BigEndian>(self.datatype, bytes)
+        } else {
+            read_value::<LittleEndian>(self.datatype, bytes)
+        }
+    }
+
+    /// Reads the field of the point starting at the given byte index as a color packed in 4
+    /// bytes, as the rgb field of PCL.
+    pub fn read_packed_rgb(&self, data: &[u8], point_start: usize) -> Color {
+        let bytes = self.bytes(data, point_start);
+        let rgb = if self.is_bigendian {
+            BigEndian::read_u32(bytes)
+        } else {
+            LittleEndian::read_u32(bytes)
+        };
+        Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
 }
 
-pub fn read_xyz(msg: &rosrust_msg::sensor_msgs::PointCloud2) -> Vec<Point3<f64>> {
-    let n_pts = msg.width * msg.height;
-    let mut points: Vec<Point3<f64>> = Vec::with_capacity(n_pts as usize);
-    let x_offset = get_channel_offset("x", &msg.fields);
-    let y_offset = get_channel_offset("y", &msg.fields);
-    let z_offset = get_channel_offset("z", &msg.fields);
-    for i in 0..n_pts {
-        let idx = i * msg.point_step;
-        points.push(Point3::new(
-            read_f32(&msg.data, idx + x_offset) as f64,
-            read_f32(&msg.data, idx + y_offset) as f64,
-            read_f32(&msg.data, idx + z_offset) as f64,
+/// Returns the byte index of the start of each point, row by row, or an error if the data is
+/// shorter than the layout of the cloud.
+pub fn point_starts(msg: &PointCloud2) -> Result<Vec<usize>, String> {
+    let (width, height) = (msg.width as usize, msg.height as usize);
+    let (point_step, row_step) = (msg.point_step as usize, msg.row_step as usize);
+    if width == 0 || height == 0 {
+        return Ok(Vec::new());
+    }
+    if width * point_step > row_step {
+        return Err(format!(
+            "Row step {} is shorter than {} points of {} bytes",
+            row_step, width, point_step
         ));
     }
-    points
+    if (height - 1) * row_step + width * point_step > msg.data.len() {
+        return Err(format!(
+            "Data of {} bytes is shorter than {} rows of {} bytes",
+            msg.data.len(),
+            height,
+            row_step
+        ));
+    }
+    Ok((0..height)
+        .flat_map(|row| (0..width).map(move |column| row * row_step + column * point_step))
+        .collect())
 }
 
-pub fn colorize_from_rgb(
-    mut points: Vec<ColoredPoint>,
-    msg: &rosrust_msg::sensor_msgs::PointCloud2,
-) -> Vec<ColoredPoint> {
-    let n_pts = msg.width * msg.height;
-    let rgb_offset = get_channel_offset("rgb", &msg.fields);
-    for i in 0..n_pts {
-        let idx = i * msg.point_step + rgb_offset;
-        points[i as usize].color = Color::Rgb(
-            msg.data[(idx + 2) as usize],
-            msg.data[(idx + 1) as usize],
-            msg.data[idx as usize],
-        );
+pub fn read_xyz(msg: &PointCloud2) -> Result<Vec<Point3<f64>>, String> {
+    let x = FieldReader::new("x", msg)?;
+    let y = FieldReader::new("y", msg)?;
+    let z = FieldReader::new("z", msg)?;
+    Ok(point_starts(msg)?
+        .into_iter()
+        .map(|start| {
+            Point3::new(
+                x.read(&msg.data, start),
+                y.read(&msg.data, start),
+                z.read(&msg.data, start),
+            )
+        })
+        .collect())
+}
+
+pub fn read_rgb(msg: &PointCloud2) -> Result<Vec<Color>, String> {
+    let rgb = FieldReader::new("rgb", msg)?;
+    if rgb.size != 4 {
+        return Err("Field rgb is not packed in 4 bytes".to_string());
     }
-    points
+    Ok(point_starts(msg)?
+        .into_iter()
+        .map(|start| rgb.read_packed_rgb(&msg.data, start))
+        .collect())
+}
+
+/// Reads the first value of the field with the given name of each point.
+pub fn read_field(name: &str, msg: &PointCloud2) -> Result<Vec<f64>, String> {
+    let field = FieldReader::new(name, msg)?;
+    Ok(point_starts(msg)?
+        .into_iter()
+        .map(|start| field.read(&msg.data, start))
+        .collect())
+}
+
+/// Names of the gradients which can color the points.
+pub const GRADIENTS: [&str; 8] = [
+    "turbo", "viridis", "plasma", "inferno", "magma", "rainbow", "spectral", "greys",
+];
+
+/// Returns the gradient with the given name, one of GRADIENTS.
+pub fn gradient(name: &str) -> Result<colorgrad::Gradient, String> {
+    match name {
+        "turbo" => Ok(colorgrad::turbo()),
+        "viridis" => Ok(colorgrad::viridis()),
+        "plasma" => Ok(colorgrad::plasma()),
+        "inferno" => Ok(colorgrad::inferno()),
+        "magma" => Ok(colorgrad::magma()),
+        "rainbow" => Ok(colorgrad::rainbow()),
+        "spectral" => Ok(colorgrad::spectral()),
+        "greys" => Ok(colorgrad::greys()),
+        _ => Err(format!(
+            "Unknown gradient {}, expected one of: {}",
+            name,
+            GRADIENTS.join(", ")
+        )),
+    }
+}
+
+/// Colors of the categories of label-like fields.
+const CATEGORY_COLORS: [Color; 10] = [
+    Color::Rgb(31, 119, 180),
+    Color::Rgb(255, 127, 14),
+    Color::Rgb(44, 160, 44),
+    Color::Rgb(214, 39, 40),
+    Color::Rgb(148, 103, 189),
+    Color::Rgb(140, 86, 75),
+    Color::Rgb(227, 119, 194),
+    Color::Rgb(127, 127, 127),
+    Color::Rgb(188, 189, 34),
+    Color::Rgb(23, 190, 207),
+];
+
+/// Returns the given range, or else the range of the finite values.
+pub fn value_range(values: &[f64], min_value: Option<f64>, max_value: Option<f64>) -> (f64, f64) {
+    let finite = values.iter().filter(|v| v.is_finite());
+    let min = min_value.unwrap_or_else(|| finite.clone().fold(f64::MAX, |min, v| min.min(*v)));
+    let max = max_value.unwrap_or_else(|| finite.fold(f64::MIN, |max, v| max.max(*v)));
+    (min, max)
+}
+
+/// Returns the colors of the values along the gradient, from the given range or else from the
+/// range of the values.
+pub fn colorize_values(
+    values: &[f64],
+    gradient: &colorgrad::Gradient,
+    min_value: Option<f64>,
+    max_value: Option<f64>,
+) -> Vec<Color> {
+    let (min, max) = value_range(values, min_value, max_value);
+    values
+        .iter()
+        .map(|v| {
+            // a constant value is shown in the middle of the gradient
+            let t = if max > min {
+                (v - min) / (max - min)
+            } else {
+                0.5
+            };
+            let c = gradient.at(t.max(0.).min(1.)).to_rgba8();
+            Color::Rgb(c[0], c[1], c[2])
+        })
+        .collect()
+}
+
+/// Returns the color of the category of each value, rounded to an integer.
+pub fn colorize_categories(values: &[f64]) -> Vec<Color> {
+    values
+        .iter()
+        .map(|v| {
+            let category = (v.round() as i64).rem_euclid(CATEGORY_COLORS.len() as i64);
+            CATEGORY_COLORS[category as usize]
+        })
+        .collect()
+}
+
+/// Render styles of the point clouds: the points, or a height map of the maximum height, the
+/// minimum height or the number of points of each cell.
+pub const STYLES: [&str; 4] = ["points", "max_height", "min_height", "density"];
+
+/// Returns true if the style draws a height map instead of the points.
+pub fn is_height_map(style: &str) -> bool {
+    STYLES[1..].contains(&style)
+}
+
+/// Rasterizes the points into a grid of cells of the given size, and returns the center of each
+/// cell with its value for the height map style.
+pub fn height_map<'a>(
+    points: impl Iterator<Item = &'a ColoredPoint>,
+    cell_size: f64,
+    style: &str,
+) -> Vec<((f64, f64), f64)> {
+    // minimum height, maximum height and number of points of each cell
+    let mut cells: HashMap<(i64, i64), (f64, f64, f64)> = HashMap::new();
+    for pt in points {
+        let cell = cells
+            .entry((
+                (pt.point.x / cell_size).floor() as i64,
+                (pt.point.y / cell_size).floor() as i64,
+            ))
+            .or_insert((f64::MAX, f64::MIN, 0.));
+        cell.0 = cell.0.min(pt.point.z);
+        cell.1 = cell.1.max(pt.point.z);
+        cell.2 += 1.;
+    }
+    cells
+        .into_iter()
+        .map(|((x, y), (min, max, count))| {
+            let value = match style {
+                "min_height" => min,
+                "density" => count,
+                _ => max,
+            };
+            (
+                ((x as f64 + 0.5) * cell_size, (y as f64 + 0.5) * cell_size),
+                value,
+            )
+        })
+        .collect()
 }
 
-pub fn colorize_points(mut points: Vec<ColoredPoint>, min_z: f64, max_z: f64) -> Vec<ColoredPoint> {
-    let grad = colorgrad::turbo();
-    for pt in points.iter_mut() {
-        let c = grad.at((pt.point.z - min_z) / (max_z - min_z)).to_rgba8();
-        pt.color = Color::Rgb(c[0], c[1], c[2]);
+/// Returns the first point of each voxel of the given size, or all the points if the size is 0.
+/// If `flat`, the points are only binned on x and y, e.g. to keep one point per dot of the
+/// viewport.
+pub fn downsample(points: Vec<ColoredPoint>, voxel_size: f64, flat: bool) -> Vec<ColoredPoint> {
+    if voxel_size <= 0. {
+        return points;
     }
+    let mut voxels = HashSet::new();
     points
+        .into_iter()
+        .filter(|pt| {
+            voxels.insert((
+                (pt.point.x / voxel_size).floor() as i64,
+                (pt.point.y / voxel_size).floor() as i64,
+                if flat {
+                    0
+                } else {
+                    (pt.point.z / voxel_size).floor() as i64
+                },
+            ))
+        })
+        .collect()
 }
 
 impl PointCloud2Listener {
     pub fn new(
         config: PointCloud2ListenerConfig,
-        tf_listener: Arc<rustros_tf::TfListener>,
+        transport: Arc<dyn Transport>,
         static_frame: String,
     ) -> PointCloud2Listener {
         let occ_points = Arc::new(RwLock::new(Vec::<ColoredPoint>::new()));
         let cb_occ_points = occ_points.clone();
+        let trail = Arc::new(RwLock::new(Trail::new(config.decay_time)));
+        let cb_trail = trail.clone();
         let str_ = static_frame.clone();
-        let local_listener = tf_listener.clone();
-        let use_rgb = config.use_rgb.clone();
-        let _sub = rosrust::subscribe(
-            &config.topic,
-            1,
-            move |cloud: rosrust_msg::sensor_msgs::PointCloud2| {
-                let mut points: Vec<ColoredPoint> = Vec::new();
-                let res = local_listener.clone().lookup_transform(
-                    &str_,
-                    &cloud.header.frame_id,
-                    cloud.header.stamp,
-                );
-                match &res {
-                    Ok(res) => res,
-                    Err(_e) => return,
-                };
-
-                let isometry = ros_transform_to_isometry(&res.unwrap().transform);
-                let mut max_z = f64::MIN;
-                let mut min_z = f64::MAX;
-                for pt in read_xyz(&cloud) {
-                    let trans_pt = isometry.transform_point(&pt);
-                    if trans_pt.z > max_z {
-                        max_z = trans_pt.z;
+        let local_transport = transport.clone();
+        let shared_config = Arc::new(RwLock::new(config.clone()));
+        let cb_shared_config = shared_config.clone();
+        let view = Arc::new(RwLock::new(ViewSettings::default()));
+        let cb_view = view.clone();
+        let cells = Arc::new(RwLock::new(Vec::new()));
+        let cb_cells = cells.clone();
+        let health = TopicHealth::new(false);
+        let cb_health = health.clone();
+        let _sub = transport
+            .subscribe(
+                &config.topic,
+                1,
+                move |cloud: rosrust_msg::sensor_msgs::PointCloud2| {
+                    cb_health.record(Some(cloud.header.stamp));
+                    let cb_config = cb_shared_config.read().unwrap().clone();
+                    let use_rgb = cb_config.use_rgb;
+                    let mut points: Vec<ColoredPoint> = Vec::new();
+                    let res = local_transport.clone().lookup_transform(
+                        &str_,
+                        &cloud.header.frame_id,
+                        cloud.header.stamp,
+                    );
+                    match &res {
+                        Ok(res) => res,
+                        Err(_e) => return,
+                    };
+
+                    let isometry = ros_transform_to_isometry(&res.unwrap().transform);
+                    let read = read_xyz(&cloud).and_then(|xyz| {
+                        let rgb = if use_rgb {
+                            Some(read_rgb(&cloud)?)
+                        } else {
+                            None
+                        };
+                        let field = match &cb_config.color_field {
+                            Some(name) if !use_rgb => Some(read_field(name, &cloud)?),
+                            _ => None,
+                        };
+                        Ok((xyz, rgb, field))
+                    });
+                    let (xyz, rgb, field) = match read {
+                        Ok(read) => read,
+                        Err(e) => {
+                            cb_health.set_error(Some(e));
+                            return;
+                        }
+                    };
+                    let gradient = match gradient(&cb_config.gradient) {
+                        Ok(gradient) => gradient,
+                        Err(e) => {
+                            cb_health.set_error(Some(e));
+                            return;
+                        }
+                    };
+                    cb_health.set_error(None);
+                    // values colored along the gradient, the height if there is no color field
+                    let mut values: Vec<f64> = Vec::new();
+                    for (i, pt) in xyz.iter().enumerate() {
+                        // clouds which are not dense contain invalid points
+                        if !(pt.x.is_finite() && pt.y.is_finite() && pt.z.is_finite()) {
+                            continue;
+                        }
+                        let trans_pt = isometry.transform_point(pt);
+                        values.push(field.as_ref().map_or(trans_pt.z, |field| field[i]));
+                        let color = rgb.as_ref().map(|rgb| rgb[i]);
+                        points.push(ColoredPoint::new(Some(trans_pt), color));
                     }
-                    if trans_pt.z < min_z {
-                        min_z = trans_pt.z;
+                    if !use_rgb {
+                        let colors = if cb_config.categorical {
+                            colorize_categories(&values)
+                        } else {
+                            colorize_values(
+                                &values,
+                                &gradient,
+                                cb_config.min_value,
+                                cb_config.max_value,
+                            )
+                        };
+                        for (pt, color) in points.iter_mut().zip(colors) {
+                            pt.color = color;
+                        }
                     }
-                    points.push(ColoredPoint::new(Some(trans_pt), None));
-                }
-                if use_rgb {
-                    points = colorize_from_rgb(points, &cloud);
-                } else {
-                    points = colorize_points(points, min_z, max_z);
-                }
-                points = points
-                    .into_iter()
-                    .filter(|n| !n.point.z.is_nan())
-                    .collect::<Vec<_>>();
-                let mut cb_occ_points = cb_occ_points.write().unwrap();
-                *cb_occ_points = points;
-            },
-        )
-        .unwrap();
+                    let view = *cb_view.read().unwrap();
+                    let region = Region::new(&cb_config, view.slice_offset);
+                    if is_height_map(&cb_config.style) {
+                        // the height maps are computed from all the drawn points
+                        let cells = if view.dot_size > 0. {
+                            height_map(
+                                points.iter().filter(|pt| region.contains(&pt.point)),
+                                view.dot_size,
+                                &cb_config.style,
+                            )
+                        } else {
+                            Vec::new()
+                        };
+                        *cb_cells.write().unwrap() = cells;
+                        cb_occ_points.write().unwrap().clear();
+                        return;
+                    }
+                    cb_cells.write().unwrap().clear();
+                    // downsampling after coloring keeps the range of the values of the whole
+                    // cloud
+                    let points = match cb_config.voxel_size {
+                        Some(voxel_size) => downsample(points, voxel_size, false),
+                        None => {
+                            // one point per dot of the viewport, among the drawn ones
+                            let mut points = points;
+                            points.retain(|pt| region.contains(&pt.point));
+                            downsample(points, view.dot_size, true)
+                        }
+                    };
+                    let mut cb_trail = cb_trail.write().unwrap();
+                    if cb_trail.is_enabled() {
+                        cb_trail.push(points.clone());
+                    }
+                    let mut cb_occ_points = cb_occ_points.write().unwrap();
+                    *cb_occ_points = points;
+                },
+            )
+            .unwrap();
 
         PointCloud2Listener {
             config,
             points: occ_points,
-            _tf_listener: tf_listener,
+            trail: trail,
+            cells: cells,
+            health: health,
+            cb_config: shared_config,
+            cb_view: view,
+            _transport: transport,
             _static_frame: static_frame.to_string(),
             _subscriber: _sub,
         }
     }
+
+    /// Updates the config of the listener of the same topic, keeping its data.
+    pub fn set_config(&mut self, config: PointCloud2ListenerConfig) {
+        self.trail
+            .write()
+            .unwrap()
+            .set_decay_time(config.decay_time);
+        *self.cb_config.write().unwrap() = config.clone();
+        self.config = config;
+    }
+
+    /// Sets the size of a dot of the viewport and the offset of its height slice, used from the
+    /// next message.
+    pub fn set_view(&self, dot_size: f64, slice_offset: f64) {
+        *self.cb_view.write().unwrap() = ViewSettings {
+            dot_size: dot_size,
+            slice_offset: slice_offset,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, offset: u32, datatype: u8) -> PointField {
+        PointField {
+            name: name.to_string(),
+            offset: offset,
+            datatype: datatype,
+            count: 1,
+        }
+    }
+
+    /// Returns an organized cloud of the given points, each row padded to the row step.
+    fn cloud(fields: Vec<PointField>, rows: Vec<Vec<Vec<u8>>>, row_step: u32) -> PointCloud2 {
+        let mut msg = PointCloud2::default();
+        msg.fields = fields;
+        msg.height = rows.len() as u32;
+        msg.width = rows[0].len() as u32;
+        msg.point_step = rows[0][0].len() as u32;
+        msg.row_step = row_step;
+        for row in rows {
+            let mut row: Vec<u8> = row.concat();
+            row.resize(row_step as usize, 0);
+            msg.data.extend(row);
+        }
+        msg
+    }
+
+    fn xyz_fields(datatype: u8, size: u32) -> Vec<PointField> {
+        vec![
+            field("x", 0, datatype),
+            field("y", size, datatype),
+            field("z", 2 * size, datatype),
+        ]
+    }
+
+    #[test]
+    fn reads_float64_fields() {
+        let point = |x: f64, y: f64, z: f64| -> Vec<u8> {
+            [x, y, z].iter().flat_map(|v| v.to_le_bytes()).collect()
+        };
+        let msg = cloud(
+            xyz_fields(PointField::FLOAT64, 8),
+            vec![vec![point(1.5, -2.25, 3.125), point(-1e10, 0., 1e-10)]],
+            48,
+        );
+        assert_eq!(
+            read_xyz(&msg).unwrap(),
+            vec![
+                Point3::new(1.5, -2.25, 3.125),
+                Point3::new(-1e10, 0., 1e-10)
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_int16_and_uint8_fields() {
+        let point = |intensity: i16, ring: u8| {
+            let mut bytes = intensity.to_le_bytes().to_vec();
+            bytes.push(ring);
+            bytes.push(0);
+            bytes
+        };
+        let msg = cloud(
+            vec![
+                field("intensity", 0, PointField::INT16),
+                field("ring", 2, PointField::UINT8),
+            ],
+            vec![vec![point(-300, 200), point(32767, 0)]],
+            8,
+        );
+        assert_eq!(read_field("intensity", &msg).unwrap(), vec![-300., 32767.]);
+        assert_eq!(read_field("ring", &msg).unwrap(), vec![200., 0.]);
+    }
+
+    #[test]
+    fn reads_big_endian_fields() {
+        let point = |x: f32, y: f32, z: f32, rgb: u32| {
+            let mut bytes: Vec<u8> = [x, y, z].iter().flat_map(|v| v.to_be_bytes()).collect();
+            bytes.extend(rgb.to_be_bytes());
+            bytes
+        };
+        let mut fields = xyz_fields(PointField::FLOAT32, 4);
+        fields.push(field("rgb", 12, PointField::UINT32));
+        let mut msg = cloud(fields, vec![vec![point(1., 2., -3.5, 0x00ff8001)]], 16);
+        msg.is_bigendian = true;
+        assert_eq!(read_xyz(&msg).unwrap(), vec![Point3::new(1., 2., -3.5)]);
+        assert_eq!(read_rgb(&msg).unwrap(), vec![Color::Rgb(255, 128, 1)]);
+    }
+
+    #[test]
+    fn skips_row_padding_of_organized_clouds() {
+        let point = |x: f32| x.to_le_bytes().to_vec();
+        let msg = cloud(
+            vec![field("x", 0, PointField::FLOAT32)],
+            vec![vec![point(1.), point(2.)], vec![point(3.), point(4.)]],
+            12,
+        );
+        assert_eq!(point_starts(&msg).unwrap(), vec![0, 4, 12, 16]);
+        assert_eq!(read_field("x", &msg).unwrap(), vec![1., 2., 3., 4.]);
+    }
+
+    #[test]
+    fn reports_missing_fields() {
+        let point =
+            |x: f32, y: f32| -> Vec<u8> { [x, y].iter().flat_map(|v| v.to_le_bytes()).collect() };
+        let msg = cloud(
+            xyz_fields(PointField::FLOAT32, 4)[..2].to_vec(),
+            vec![vec![point(1., 2.)]],
+            8,
+        );
+        assert_eq!(read_xyz(&msg).unwrap_err(), "Missing field z");
+        assert!(FieldReader::new("rgb", &msg).is_err());
+    }
+
+    #[test]
+    fn reports_truncated_data() {
+        let point = |x: f32| x.to_le_bytes().to_vec();
+        let mut msg = cloud(
+            vec![field("x", 0, PointField::FLOAT32)],
+            vec![vec![point(1.), point(2.)]],
+            8,
+        );
+        msg.data.truncate(6);
+        assert!(point_starts(&msg).is_err());
+    }
+
+    #[test]
+    fn downsamples_on_x_and_y_if_flat() {
+        let points: Vec<ColoredPoint> = [(0.1, 0.1, 0.1), (0.2, 0.2, 1.5), (1.1, 0.1, 0.1)]
+            .iter()
+            .map(|(x, y, z)| ColoredPoint::new(Some(Point3::new(*x, *y, *z)), None))
+            .collect();
+        assert_eq!(downsample(points.clone(), 1., false).len(), 3);
+        let flat: Vec<Point3<f64>> = downsample(points, 1., true)
+            .into_iter()
+            .map(|pt| pt.point)
+            .collect();
+        assert_eq!(
+            flat,
+            vec![Point3::new(0.1, 0.1, 0.1), Point3::new(1.1, 0.1, 0.1)]
+        );
+    }
 }
@@ -1,12 +1,28 @@
 use crate::config::{Color, PoseListenerConfig};
+use crate::health::TopicHealth;
 use crate::transformation::ros_pose_to_isometry;
+use crate::transport::{Subscription, Transport};
 use nalgebra::geometry::{Isometry3, Point3};
 use std::option::Option;
 use std::sync::{Arc, RwLock};
 use tui::style;
 use tui::widgets::canvas::Line;
 
-use rosrust;
+/// Styles of the PoseStamped topics, the first one being the default style.
+pub const POSE_STAMPED_STYLES: [&str; 2] = ["axis", "arrow"];
+/// Styles of the PoseArray topics, the first one being the default style.
+pub const POSE_ARRAY_STYLES: [&str; 2] = ["arrow", "axis"];
+/// Styles of the Path topics, the first one being the default style.
+pub const PATH_STYLES: [&str; 3] = ["line", "arrow", "axis"];
+
+/// Returns the style if it is one of the given styles, or else the default style.
+pub fn valid_style(styles: &[&'static str], style: &str) -> &'static str {
+    styles
+        .iter()
+        .find(|s| **s == style)
+        .copied()
+        .unwrap_or(styles[0])
+}
 
 fn pose_to_arrow(pose: &Isometry3<f64>, length: f64, color: &Color) -> Vec<Line> {
     let mut lines: Vec<Line> = Vec::new();
@@ -87,37 +103,43 @@ fn poses_to_lines(poses: &Vec<Isometry3<f64>>, color: &Color) -> Vec<Line> {
 }
 
 pub struct PoseStampedListener {
-    config: PoseListenerConfig,
+    pub config: PoseListenerConfig,
     pose: Arc<RwLock<Option<Isometry3<f64>>>>,
-    _subscriber: rosrust::Subscriber,
+    pub health: TopicHealth,
+    _subscriber: Subscription,
 }
 
 impl PoseStampedListener {
-    pub fn new(config: PoseListenerConfig) -> PoseStampedListener {
+    pub fn new(config: PoseListenerConfig, transport: Arc<dyn Transport>) -> PoseStampedListener {
         let pose = Arc::new(RwLock::new(None));
         let cb_pose = pose.clone();
-        let sub = rosrust::subscribe(
-            &config.topic,
-            2,
-            move |pose_msg: rosrust_msg::geometry_msgs::PoseStamped| {
-                let pose_iso = ros_pose_to_isometry(&pose_msg.pose);
-                *cb_pose.write().unwrap() = Some(pose_iso);
-            },
-        )
-        .unwrap();
+        let health = TopicHealth::new(false);
+        let cb_health = health.clone();
+        let sub = transport
+            .subscribe(
+                &config.topic,
+                2,
+                move |pose_msg: rosrust_msg::geometry_msgs::PoseStamped| {
+                    cb_health.record(Some(pose_msg.header.stamp));
+                    let pose_iso = ros_pose_to_isometry(&pose_msg.pose);
+                    *cb_pose.write().unwrap() = Some(pose_iso);
+                },
+            )
+            .unwrap();
 
         PoseStampedListener {
             config: config,
             pose: pose,
+            health: health,
             _subscriber: sub,
         }
     }
 
     pub fn get_lines(&self) -> Vec<Line> {
         match *self.pose.read().unwrap() {
-            Some(p) => match self.config.style.as_str() {
+            Some(p) => match valid_style(&POSE_STAMPED_STYLES, &self.config.style) {
                 "arrow" => pose_to_arrow(&p, self.config.length, &self.config.color),
-                "axes" => pose_to_axes(&p, self.config.length),
+                "axis" => pose_to_axes(&p, self.config.length),
                 _ => Vec::new(),
             },
             None => Vec::new(),
@@ -126,32 +148,38 @@ impl PoseStampedListener {
 }
 
 pub struct PoseArrayListener {
-    config: PoseListenerConfig,
+    pub config: PoseListenerConfig,
     poses: Arc<RwLock<Vec<Isometry3<f64>>>>,
-    _subscriber: rosrust::Subscriber,
+    pub health: TopicHealth,
+    _subscriber: Subscription,
 }
 
 impl PoseArrayListener {
-    pub fn new(config: PoseListenerConfig) -> PoseArrayListener {
+    pub fn new(config: PoseListenerConfig, transport: Arc<dyn Transport>) -> PoseArrayListener {
         let poses = Arc::new(RwLock::new(Vec::<Isometry3<f64>>::new()));
         let cb_poses = poses.clone();
-        let sub = rosrust::subscribe(
-            &config.topic,
-            2,
-            move |pose_array: rosrust_msg::geometry_msgs::PoseArray| {
-                let poses_iso = pose_array
-                    .poses
-                    .into_iter()
-                    .map(|p| ros_pose_to_isometry(&p))
-                    .collect();
-                *cb_poses.write().unwrap() = poses_iso;
-            },
-        )
-        .unwrap();
+        let health = TopicHealth::new(false);
+        let cb_health = health.clone();
+        let sub = transport
+            .subscribe(
+                &config.topic,
+                2,
+                move |pose_array: rosrust_msg::geometry_msgs::PoseArray| {
+                    cb_health.record(Some(pose_array.header.stamp));
+                    let poses_iso = pose_array
+                        .poses
+                        .into_iter()
+                        .map(|p| ros_pose_to_isometry(&p))
+                        .collect();
+                    *cb_poses.write().unwrap() = poses_iso;
+                },
+            )
+            .unwrap();
 
         PoseArrayListener {
             config: config,
             poses: poses,
+            health: health,
             _subscriber: sub,
         }
     }
@@ -160,7 +188,7 @@ impl PoseArrayListener {
         if self.poses.read().unwrap().is_empty() {
             return Vec::new();
         }
-        match self.config.style.as_str() {
+        match valid_style(&POSE_ARRAY_STYLES, &self.config.style) {
             "arrow" => self
                 .poses
                 .read()
@@ -187,32 +215,38 @@ impl PoseArrayListener {
 }
 
 pub struct PathListener {
-    config: PoseListenerConfig,
+    pub config: PoseListenerConfig,
     poses: Arc<RwLock<Vec<Isometry3<f64>>>>,
-    _subscriber: rosrust::Subscriber,
+    pub health: TopicHealth,
+    _subscriber: Subscription,
 }
 
 impl PathListener {
-    pub fn new(config: PoseListenerConfig) -> PathListener {
+    pub fn new(config: PoseListenerConfig, transport: Arc<dyn Transport>) -> PathListener {
         let poses = Arc::new(RwLock::new(Vec::<Isometry3<f64>>::new()));
         let cb_poses = poses.clone();
-        let sub = rosrust::subscribe(
-            &config.topic,
-            2,
-            move |path: rosrust_msg::nav_msgs::Path| {
-                let poses_iso = path
-                    .poses
-                    .into_iter()
-                    .map(|p| ros_pose_to_isometry(&p.pose))
-                    .collect();
-                *cb_poses.write().unwrap() = poses_iso;
-            },
-        )
-        .unwrap();
+        let health = TopicHealth::new(false);
+        let cb_health = health.clone();
+        let sub = transport
+            .subscribe(
+                &config.topic,
+                2,
+                move |path: rosrust_msg::nav_msgs::Path| {
+                    cb_health.record(Some(path.header.stamp));
+                    let poses_iso = path
+                        .poses
+                        .into_iter()
+                        .map(|p| ros_pose_to_isometry(&p.pose))
+                        .collect();
+                    *cb_poses.write().unwrap() = poses_iso;
+                },
+            )
+            .unwrap();
 
         PathListener {
             config: config,
             poses: poses,
+            health: health,
             _subscriber: sub,
         }
     }
@@ -221,7 +255,7 @@ impl PathListener {
         if self.poses.read().unwrap().is_empty() {
             return Vec::new();
         }
-        match self.config.style.as_str() {
+        match valid_style(&PATH_STYLES, &self.config.style) {
             "arrow" => self
                 .poses
                 .read()
@@ -0,0 +1,445 @@
+//! Recording of the visualized topics to a ROS1 bag file.
+//!
+//! The bag is written in chunks, without compression. The index is written when the
+//! recording is stopped, so a bag whose recording was interrupted cannot be played.
+
+use crate::transport::{resolve_topic, MessageDescription, Subscription, Transport};
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8] = b"#ROSBAG V2.0\n";
+const BAG_HEADER_LENGTH: usize = 4096;
+const CHUNK_SIZE: usize = 768 * 1024;
+
+const OP_MSG_DATA: u8 = 0x02;
+const OP_BAG_HEADER: u8 = 0x03;
+const OP_INDEX_DATA: u8 = 0x04;
+const OP_CHUNK: u8 = 0x05;
+const OP_CHUNK_INFO: u8 = 0x06;
+const OP_CONNECTION: u8 = 0x07;
+
+fn time_bytes(time: u64) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8);
+    bytes
+        .write_u32::<LittleEndian>((time / 1_000_000_000) as u32)
+        .unwrap();
+    bytes
+        .write_u32::<LittleEndian>((time % 1_000_000_000) as u32)
+        .unwrap();
+    bytes
+}
+
+fn u32_bytes(value: u32) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+/// Serializes header fields, each one prefixed by its length.
+fn header_bytes(fields: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (name, value) in fields {
+        bytes
+            .write_u32::<LittleEndian>((name.len() + 1 + value.len()) as u32)
+            .unwrap();
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.push(b'=');
+        bytes.extend_from_slice(value);
+    }
+    bytes
+}
+
+fn write_record<W: Write>(out: &mut W, header: &[(&str, Vec<u8>)], data: &[u8]) -> io::Result<()> {
+    let header = header_bytes(header);
+    out.write_u32::<LittleEndian>(header.len() as u32)?;
+    out.write_all(&header)?;
+    out.write_u32::<LittleEndian>(data.len() as u32)?;
+    out.write_all(data)
+}
+
+struct Connection {
+    topic: String,
+    description: MessageDescription,
+    written: bool,
+}
+
+impl Connection {
+    fn write<W: Write>(&self, out: &mut W, id: u32) -> io::Result<()> {
+        let data = header_bytes(&[
+            ("topic", self.topic.as_bytes().to_vec()),
+            ("type", self.description.datatype.as_bytes().to_vec()),
+            ("md5sum", self.description.md5sum.as_bytes().to_vec()),
+            (
+                "message_definition",
+                self.description.definition.as_bytes().to_vec(),
+            ),
+        ]);
+        write_record(
+            out,
+            &[
+                ("op", vec![OP_CONNECTION]),
+                ("conn", u32_bytes(id)),
+                ("topic", self.topic.as_bytes().to_vec()),
+            ],
+            &data,
+        )
+    }
+}
+
+struct ChunkInfo {
+    position: u64,
+    start: u64,
+    end: u64,
+    message_counts: Vec<(u32, u32)>,
+}
+
+/// Writes messages to a bag file.
+pub(crate) struct BagWriter {
+    file: BufWriter<File>,
+    position: u64,
+    connections: Vec<Connection>,
+    connection_ids: HashMap<String, u32>,
+    chunk: Vec<u8>,
+    /// Time and offset in the current chunk of its messages, by connection.
+    chunk_index: HashMap<u32, Vec<(u64, u32)>>,
+    chunk_infos: Vec<ChunkInfo>,
+}
+
+impl BagWriter {
+    pub(crate) fn create(path: &Path) -> io::Result<BagWriter> {
+        let mut writer = BagWriter {
+            file: BufWriter::new(File::create(path)?),
+            position: 0,
+            connections: Vec::new(),
+            connection_ids: HashMap::new(),
+            chunk: Vec::new(),
+            chunk_index: HashMap::new(),
+            chunk_infos: Vec::new(),
+        };
+        writer.file.write_all(MAGIC)?;
+        writer.write_bag_header(0)?;
+        writer.position = (MAGIC.len() + BAG_HEADER_LENGTH) as u64;
+        Ok(writer)
+    }
+
+    fn write_bag_header(&mut self, index_position: u64) -> io::Result<()> {
+        let header = [
+            ("index_pos", index_position.to_le_bytes().to_vec()),
+            ("conn_count", u32_bytes(self.connections.len() as u32)),
+            ("chunk_count", u32_bytes(self.chunk_infos.len() as u32)),
+            ("op", vec![OP_BAG_HEADER]),
+        ];
+        // the bag header is padded, so that it can be rewritten in place
+        let padding = BAG_HEADER_LENGTH - 8 - header_bytes(&header).len();
+        write_record(&mut self.file, &header, &vec![b' '; padding])
+    }
+
+    /// Returns the size of the bag, including the messages not yet written to the file.
+    fn size(&self) -> u64 {
+        self.position + self.chunk.len() as u64
+    }
+
+    pub(crate) fn add_connection(&mut self, topic: &str, description: MessageDescription) {
+        let id = self.connections.len() as u32;
+        self.connection_ids.insert(topic.to_string(), id);
+        self.connections.push(Connection {
+            topic: topic.to_string(),
+            description: description,
+            written: false,
+        });
+    }
+
+    pub(crate) fn write_message(&mut self, topic: &str, time: u64, data: &[u8]) -> io::Result<()> {
+        let id = match self.connection_ids.get(topic) {
+            Some(id) => *id,
+            None => return Ok(()),
+        };
+        if !self.connections[id as usize].written {
+            // the connection is written in the chunk of its first message
+            self.connections[id as usize].write(&mut self.chunk, id)?;
+            self.connections[id as usize].written = true;
+        }
+        self.chunk_index
+            .entry(id)
+            .or_insert_with(Vec::new)
+            .push((time, self.chunk.len() as u32));
+        write_record(
+            &mut self.chunk,
+            &[
+                ("op", vec![OP_MSG_DATA]),
+                ("conn", u32_bytes(id)),
+                ("time", time_bytes(time)),
+            ],
+            data,
+        )?;
+        if self.chunk.len() >= CHUNK_SIZE {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    fn flush_chunk(&mut self) -> io::Result<()> {
+        if self.chunk_index.is_empty() {
+            return Ok(());
+        }
+        let times = self.chunk_index.values().flatten().map(|(time, _)| *time);
+        let mut info = ChunkInfo {
+            position: self.position,
+            start: times.clone().min().unwrap(),
+            end: times.max().unwrap(),
+            message_counts: Vec::new(),
+        };
+        let mut records = Vec::new();
+        write_record(
+            &mut records,
+            &[
+                ("op", vec![OP_CHUNK]),
+                ("compression", b"none".to_vec()),
+                ("size", u32_bytes(self.chunk.len() as u32)),
+            ],
+            &self.chunk,
+        )?;
+        let mut ids: Vec<&u32> = self.chunk_index.keys().collect();
+        ids.sort();
+        for id in ids {
+            let entries = &self.chunk_index[id];
+            let mut data = Vec::new();
+            for (time, offset) in entries {
+                data.extend(time_bytes(*time));
+                data.extend(u32_bytes(*offset));
+            }
+            write_record(
+                &mut records,
+                &[
+                    ("op", vec![OP_INDEX_DATA]),
+                    ("ver", u32_bytes(1)),
+                    ("conn", u32_bytes(*id)),
+                    ("count", u32_bytes(entries.len() as u32)),
+                ],
+                &data,
+            )?;
+            info.message_counts.push((*id, entries.len() as u32));
+        }
+        self.file.write_all(&records)?;
+        self.position += records.len() as u64;
+        self.chunk_infos.push(info);
+        self.chunk.clear();
+        self.chunk_index.clear();
+        Ok(())
+    }
+
+    /// Writes the pending messages and the index of the bag.
+    pub(crate) fn close(&mut self) -> io::Result<()> {
+        self.flush_chunk()?;
+        let index_position = self.position;
+        let mut records = Vec::new();
+        for (id, connection) in self.connections.iter().enumerate() {
+            connection.write(&mut records, id as u32)?;
+        }
+        for info in &self.chunk_infos {
+            let mut data = Vec::new();
+            for (id, count) in &info.message_counts {
+                data.extend(u32_bytes(*id));
+                data.extend(u32_bytes(*count));
+            }
+            write_record(
+                &mut records,
+                &[
+                    ("op", vec![OP_CHUNK_INFO]),
+                    ("ver", u32_bytes(1)),
+                    ("chunk_pos", info.position.to_le_bytes().to_vec()),
+                    ("start_time", time_bytes(info.start)),
+                    ("end_time", time_bytes(info.end)),
+                    ("count", u32_bytes(info.message_counts.len() as u32)),
+                ],
+                &data,
+            )?;
+        }
+        self.file.write_all(&records)?;
+        self.position += records.len() as u64;
+        self.file.seek(SeekFrom::Start(MAGIC.len() as u64))?;
+        self.write_bag_header(index_position)?;
+        self.file.flush()
+    }
+}
+
+struct RecorderState {
+    writer: BagWriter,
+    error: Option<String>,
+    closed: bool,
+}
+
+/// Records the messages received on a set of topics to a bag file, until it is dropped.
+pub struct BagRecorder {
+    path: PathBuf,
+    state: Arc<Mutex<RecorderState>>,
+    subscriptions: Vec<Subscription>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+}
+
+impl BagRecorder {
+    /// Creates the bag file and subscribes to the topics, as well as to `/tf` and `/tf_static`.
+    pub fn start(
+        path: &Path,
+        transport: &Arc<dyn Transport>,
+        topics: Vec<(String, MessageDescription)>,
+    ) -> Result<BagRecorder, String> {
+        let writer =
+            BagWriter::create(path).map_err(|e| format!("Could not create {:?}: {}", path, e))?;
+        let state = Arc::new(Mutex::new(RecorderState {
+            writer: writer,
+            error: None,
+            closed: false,
+        }));
+        let tf_description = MessageDescription::of::<rosrust_msg::tf2_msgs::TFMessage>();
+        let mut all_topics = vec![
+            ("/tf".to_string(), tf_description.clone()),
+            ("/tf_static".to_string(), tf_description),
+        ];
+        all_topics.extend(topics);
+        let mut subscriptions = Vec::new();
+        for (topic, description) in all_topics {
+            let topic = resolve_topic(&topic);
+            {
+                let mut state = state.lock().unwrap();
+                if state.writer.connection_ids.contains_key(&topic) {
+                    continue;
+                }
+                state.writer.add_connection(&topic, description);
+            }
+            let callback_state = state.clone();
+            let callback_topic = topic.clone();
+            let subscription = transport
+                .subscribe_raw(
+                    &topic,
+                    100,
+                    Box::new(move |data: &[u8]| {
+                        let mut state = callback_state.lock().unwrap();
+                        if let Err(e) = state.writer.write_message(&callback_topic, now(), data) {
+                            state.error = Some(e.to_string());
+                        }
+                    }),
+                )
+                .map_err(|e| format!("Could not subscribe to {}: {:?}", topic, e))?;
+            subscriptions.push(subscription);
+        }
+        Ok(BagRecorder {
+            path: path.to_path_buf(),
+            state: state,
+            subscriptions: subscriptions,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the current size of the bag, in bytes.
+    pub fn size(&self) -> u64 {
+        self.state.lock().unwrap().writer.size()
+    }
+
+    /// Returns the last error that occurred while writing the bag, if any.
+    pub fn error(&self) -> Option<String> {
+        self.state.lock().unwrap().error.clone()
+    }
+
+    /// Stops the recording and writes the index of the bag.
+    pub fn stop(mut self) -> Result<(), String> {
+        self.close()
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        self.subscriptions.clear();
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Ok(());
+        }
+        state.closed = true;
+        state
+            .writer
+            .close()
+            .map_err(|e| format!("Could not write {:?}: {}", self.path, e))
+    }
+}
+
+impl Drop for BagRecorder {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosbag::{ChunkRecord, MessageRecord, RosBag};
+    use rosrust_msg::sensor_msgs::LaserScan;
+    use rosrust_msg::std_msgs::String as StringMsg;
+
+    #[test]
+    fn written_bag_is_read_back() {
+        // the large messages fill more than one chunk
+        let messages: Vec<(&str, u64, Vec<u8>)> = vec![
+            ("/chatter", 1_000_000_000, b"first".to_vec()),
+            ("/scan", 1_500_000_001, vec![1; 500 * 1024]),
+            ("/chatter", 2_000_000_000, b"second".to_vec()),
+            ("/scan", 2_500_000_002, vec![2; 500 * 1024]),
+            ("/scan", 3_500_000_003, vec![3; 500 * 1024]),
+        ];
+        let path = std::env::temp_dir().join("termviz_record_test.bag");
+        let mut writer = BagWriter::create(&path).unwrap();
+        writer.add_connection("/chatter", MessageDescription::of::<StringMsg>());
+        writer.add_connection("/scan", MessageDescription::of::<LaserScan>());
+        for (topic, time, data) in &messages {
+            writer.write_message(topic, *time, data).unwrap();
+        }
+        writer.close().unwrap();
+
+        let bag = RosBag::new(&path).unwrap();
+        let mut connections: HashMap<u32, (String, String)> = HashMap::new();
+        let mut read = Vec::new();
+        let mut chunk_count = 0;
+        for record in bag.chunk_records() {
+            if let ChunkRecord::Chunk(chunk) = record.unwrap() {
+                chunk_count += 1;
+                for msg in chunk.messages() {
+                    match msg.unwrap() {
+                        MessageRecord::Connection(conn) => {
+                            connections
+                                .insert(conn.id, (conn.topic.to_string(), conn.tp.to_string()));
+                        }
+                        MessageRecord::MessageData(msg_data) => {
+                            read.push((msg_data.conn_id, msg_data.time, msg_data.data.to_vec()))
+                        }
+                    }
+                }
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(chunk_count, 2);
+        let mut topics: Vec<(String, String)> = connections.values().cloned().collect();
+        topics.sort();
+        assert_eq!(
+            topics,
+            vec![
+                ("/chatter".to_string(), "std_msgs/String".to_string()),
+                ("/scan".to_string(), "sensor_msgs/LaserScan".to_string()),
+            ]
+        );
+        let read: Vec<(&str, u64, Vec<u8>)> = read
+            .into_iter()
+            .map(|(id, time, data)| (connections[&id].0.as_str(), time, data))
+            .collect();
+        assert_eq!(read, messages);
+    }
+}
@@ -0,0 +1,390 @@
+//! Headless rendering tests of the app modes.
+//!
+//! The app is rendered on a `TestBackend`, with its data published on a `MockTransport`, so no
+//! ROS master is needed. Each rendered buffer is compared against its golden snapshot in
+//! `snapshots/`. A missing snapshot fails the test; the snapshots are recorded with
+//! `TERMVIZ_BLESS=1 cargo test`, for a new test or after an intended rendering change.
+
+use crate::app::App;
+use crate::app_modes::input;
+use crate::bag::BagPlayer;
+use crate::config::TermvizConfig;
+use crate::listeners::Listeners;
+use crate::pointcloud::ColoredPoint;
+use crate::record::BagWriter;
+use crate::transformation;
+use crate::transport::{MessageDescription, MockTransport, Transport};
+use nalgebra::{Isometry2, Point3, Vector2};
+use rosrust::RosMsg;
+use rosrust_msg::geometry_msgs::{Pose, PoseArray, PoseStamped, TransformStamped};
+use rosrust_msg::nav_msgs::{OccupancyGrid, Path};
+use rosrust_msg::sensor_msgs::LaserScan;
+use rosrust_msg::tf2_msgs::TFMessage;
+use rosrust_msg::visualization_msgs::Marker;
+use std::f32::consts::PI;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tui::backend::TestBackend;
+use tui::buffer::Buffer;
+use tui::style::Color;
+use tui::Terminal;
+
+const WIDTH: u16 = 80;
+const HEIGHT: u16 = 30;
+
+fn pose(x: f64, y: f64, yaw: f64) -> Pose {
+    let tf = transformation::iso2d_to_ros(&Isometry2::new(Vector2::new(x, y), yaw));
+    let mut pose = Pose::default();
+    pose.position.x = tf.translation.x;
+    pose.position.y = tf.translation.y;
+    pose.orientation = tf.rotation;
+    pose
+}
+
+fn transform(parent: &str, child: &str, x: f64, y: f64, yaw: f64) -> TransformStamped {
+    let mut tf = TransformStamped::default();
+    tf.header.frame_id = parent.to_string();
+    tf.child_frame_id = child.to_string();
+    tf.transform = transformation::iso2d_to_ros(&Isometry2::new(Vector2::new(x, y), yaw));
+    tf
+}
+
+fn set_transform(transport: &MockTransport, parent: &str, child: &str, x: f64, y: f64, yaw: f64) {
+    transport.set_transform(transform(parent, child, x, y, yaw), false);
+}
+
+fn test_scan() -> LaserScan {
+    let mut scan = LaserScan::default();
+    scan.header.frame_id = "laser".to_string();
+    scan.angle_min = -PI / 2.0;
+    scan.angle_increment = PI / 90.0;
+    scan.range_min = 0.1;
+    scan.range_max = 10.0;
+    scan.ranges = (0..=90).map(|i| 1.5 + (i % 10) as f32 * 0.05).collect();
+    scan
+}
+
+/// Publishes a room, a laser scan, a marker, poses and a path, and fills a point cloud.
+fn publish_data(transport: &MockTransport, listeners: &Listeners) {
+    set_transform(transport, "map", "base_link", 1.0, 0.5, 0.3);
+    set_transform(transport, "base_link", "laser", 0.2, 0.0, 0.0);
+
+    // 8m x 8m room centered on the origin, with a resolution of 0.1m
+    let mut map = OccupancyGrid::default();
+    map.header.frame_id = "map".to_string();
+    map.info.resolution = 0.1;
+    map.info.width = 81;
+    map.info.height = 81;
+    map.info.origin = pose(-4.0, -4.0, 0.0);
+    map.data = (0..81 * 81)
+        .map(|i| {
+            let (row, column) = (i / 81, i % 81);
+            if row == 0 || row == 80 || column == 0 || column == 80 {
+                100
+            } else {
+                0
+            }
+        })
+        .collect();
+    transport.publish("map", &map);
+
+    transport.publish("scan", &test_scan());
+
+    let mut pose_stamped = PoseStamped::default();
+    pose_stamped.header.frame_id = "map".to_string();
+    pose_stamped.pose = pose(-1.0, -2.0, 0.8);
+    transport.publish("pose_stamped", &pose_stamped);
+
+    let mut pose_array = PoseArray::default();
+    pose_array.header.frame_id = "map".to_string();
+    pose_array.poses = (0..3).map(|i| pose(2.0, -2.0 + i as f64, 0.0)).collect();
+    transport.publish("pose_array", &pose_array);
+
+    let mut path = Path::default();
+    path.header.frame_id = "map".to_string();
+    path.poses = (0..10)
+        .map(|i| {
+            let mut p = PoseStamped::default();
+            p.pose = pose(1.0 + i as f64 * 0.3, 0.5 + i as f64 * 0.2, 0.0);
+            p
+        })
+        .collect();
+    transport.publish("path", &path);
+
+    let mut marker = Marker::default();
+    marker.header.frame_id = "map".to_string();
+    marker.ns = "test".to_string();
+    marker.type_ = Marker::CUBE as i32;
+    marker.action = Marker::ADD as i32;
+    marker.pose = pose(-2.0, 2.0, 0.0);
+    marker.scale.x = 0.8;
+    marker.scale.y = 0.8;
+    marker.scale.z = 0.8;
+    marker.color.g = 1.0;
+    marker.color.a = 1.0;
+    transport.publish("marker", &marker);
+
+    *listeners.pointclouds[0].points.write().unwrap() = (0..20)
+        .map(|i| {
+            ColoredPoint::new(
+                Some(Point3::new(-3.0 + i as f64 * 0.1, -3.0, 0.0)),
+                Some(Color::Cyan),
+            )
+        })
+        .collect();
+}
+
+fn footprint() -> Vec<(f64, f64)> {
+    vec![(0.3, 0.2), (-0.3, 0.2), (-0.3, -0.2), (0.3, -0.2)]
+}
+
+fn test_listeners(transport: Arc<dyn Transport>, config: &TermvizConfig) -> Listeners {
+    Listeners::new(
+        transport,
+        config.fixed_frame.clone(),
+        config.laser_topics.clone(),
+        config.marker_topics.clone(),
+        config.marker_array_topics.clone(),
+        config.map_topics.clone(),
+        config.pose_stamped_topics.clone(),
+        config.pose_array_topics.clone(),
+        config.pointcloud2_topics.clone(),
+        config.path_topics.clone(),
+    )
+}
+
+fn test_app() -> App<TestBackend> {
+    let config = TermvizConfig::default();
+    let mock = MockTransport::new();
+    let transport: Arc<dyn Transport> = Arc::new(mock.clone());
+    let listeners = test_listeners(transport.clone(), &config);
+    publish_data(&mock, &listeners);
+    App::with_listeners(
+        transport,
+        config,
+        std::env::temp_dir().join("termviz_test.yml"),
+        listeners,
+        &footprint(),
+        (WIDTH, HEIGHT),
+        None,
+    )
+}
+
+/// Writes a bag with the robot and a laser scan at its start, and the robot moving ten seconds
+/// later, so that the later messages are not reached during a test.
+fn write_test_bag(path: &std::path::Path) {
+    const START: u64 = 100_000_000_000;
+    let tf_message = |transforms| TFMessage {
+        transforms: transforms,
+    };
+    let mut writer = BagWriter::create(path).unwrap();
+    writer.add_connection("/tf", MessageDescription::of::<TFMessage>());
+    writer.add_connection("/scan", MessageDescription::of::<LaserScan>());
+    let messages = vec![
+        (
+            "/tf",
+            START,
+            tf_message(vec![
+                transform("map", "base_link", 1.0, 0.5, 0.3),
+                transform("base_link", "laser", 0.2, 0.0, 0.0),
+            ])
+            .encode_vec()
+            .unwrap(),
+        ),
+        ("/scan", START, test_scan().encode_vec().unwrap()),
+        (
+            "/tf",
+            START + 10_000_000_000,
+            tf_message(vec![transform("map", "base_link", 3.0, 0.5, 0.3)])
+                .encode_vec()
+                .unwrap(),
+        ),
+    ];
+    for (topic, time, data) in messages {
+        writer.write_message(topic, time, &data).unwrap();
+    }
+    writer.close().unwrap();
+}
+
+/// Returns an app playing the test bag, written to a file of the given name, paused at its
+/// start.
+fn playback_app(name: &str) -> App<TestBackend> {
+    let config = TermvizConfig::default();
+    let mock = MockTransport::new();
+    let transport: Arc<dyn Transport> = Arc::new(mock.clone());
+    // the listeners subscribe before the playback starts, so that they get all its messages
+    let listeners = test_listeners(transport.clone(), &config);
+    let path = std::env::temp_dir().join(format!("termviz_{}.bag", name));
+    write_test_bag(&path);
+    // the player is opened paused, and only publishes the messages of the start of the bag,
+    // so that the playback thread does not race the test
+    let player = BagPlayer::open(&path, mock, true).unwrap();
+    player.seek_to_start();
+    let mut app = App::with_listeners(
+        transport,
+        config,
+        std::env::temp_dir().join("termviz_test.yml"),
+        listeners,
+        &footprint(),
+        (WIDTH, HEIGHT),
+        Some(player),
+    );
+    app.handle_input(&input::PLAYBACK_MODE.to_string());
+    app
+}
+
+/// Serializes the symbols of the buffer, followed by the runs of non-default foreground colors.
+fn buffer_to_string(buffer: &Buffer) -> String {
+    let mut symbols = String::new();
+    let mut colors = String::new();
+    for y in 0..buffer.area.height {
+        let mut run_start = 0;
+        for x in 0..buffer.area.width {
+            let cell = buffer.get(x, y);
+            symbols.push_str(&cell.symbol);
+            let is_run_end = x + 1 == buffer.area.width || buffer.get(x + 1, y).fg != cell.fg;
+            if is_run_end {
+                if cell.fg != Color::Reset {
+                    colors.push_str(&format!("{}: {}..{} {:?}\n", y, run_start, x + 1, cell.fg));
+                }
+                run_start = x + 1;
+            }
+        }
+        symbols.push('\n');
+    }
+    symbols + "\n" + &colors
+}
+
+fn render(app: &App<TestBackend>) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+    terminal.draw(|f| app.draw(f)).unwrap();
+    buffer_to_string(terminal.backend().buffer())
+}
+
+fn assert_snapshot(name: &str, rendered: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    if std::env::var("TERMVIZ_BLESS").is_ok() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, rendered).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "Missing snapshot {:?}, record it with TERMVIZ_BLESS=1 cargo test",
+            path
+        )
+    });
+    assert_eq!(
+        expected, rendered,
+        "Rendering differs from the snapshot {:?}",
+        path
+    );
+}
+
+fn switch_to_mode(app: &mut App<TestBackend>, mode: usize) {
+    app.handle_input(&mode.to_string());
+}
+
+#[test]
+fn send_pose_mode() {
+    let app = test_app();
+    assert_snapshot("send_pose_mode", &render(&app));
+}
+
+#[test]
+fn send_pose_mode_with_ghost() {
+    let mut app = test_app();
+    app.handle_input(&input::UP.to_string());
+    app.handle_input(&input::ROTATE_LEFT.to_string());
+    assert_snapshot("send_pose_mode_with_ghost", &render(&app));
+}
+
+#[test]
+fn teleoperate_mode() {
+    let mut app = test_app();
+    switch_to_mode(&mut app, 2);
+    assert_snapshot("teleoperate_mode", &render(&app));
+}
+
+#[test]
+fn teleoperate_mode_zoomed_out() {
+    let mut app = test_app();
+    switch_to_mode(&mut app, 2);
+    app.handle_input(&input::ZOOM_OUT.to_string());
+    app.handle_input(&input::ZOOM_OUT.to_string());
+    assert_snapshot("teleoperate_mode_zoomed_out", &render(&app));
+}
+
+#[test]
+fn image_mode() {
+    let mut app = test_app();
+    switch_to_mode(&mut app, 3);
+    assert_snapshot("image_mode", &render(&app));
+}
+
+#[test]
+fn topic_manager_mode() {
+    let mut app = test_app();
+    switch_to_mode(&mut app, 4);
+    assert_snapshot("topic_manager_mode", &render(&app));
+}
+
+#[test]
+fn explore_mode() {
+    let mut app = test_app();
+    switch_to_mode(&mut app, 5);
+    app.handle_input(&input::LEFT.to_string());
+    app.handle_input(&input::LEFT.to_string());
+    assert_snapshot("explore_mode", &render(&app));
+}
+
+#[test]
+fn layers_mode() {
+    let mut app = test_app();
+    switch_to_mode(&mut app, 7);
+    // hides the first layer and recolors the second one
+    app.handle_input(&input::CONFIRM.to_string());
+    app.handle_input(&input::DOWN.to_string());
+    app.handle_input(&input::ROTATE_RIGHT.to_string());
+    assert_snapshot("layers_mode", &render(&app));
+}
+
+#[test]
+fn marker_namespaces_mode() {
+    let mut app = test_app();
+    switch_to_mode(&mut app, 6);
+    assert_snapshot("marker_namespaces_mode", &render(&app));
+}
+
+#[test]
+fn topic_health_mode() {
+    let mut app = test_app();
+    switch_to_mode(&mut app, 8);
+    assert_snapshot("topic_health_mode", &render(&app));
+}
+
+#[test]
+fn playback_mode() {
+    let app = playback_app("playback_mode");
+    assert_snapshot("playback_mode", &render(&app));
+}
+
+#[test]
+fn playback_mode_seeked_back() {
+    let mut app = playback_app("playback_mode_seeked_back");
+    // moves past the end of the bag and back, which rebuilds the transforms
+    app.handle_input(&input::RIGHT.to_string());
+    app.handle_input(&input::RIGHT.to_string());
+    app.handle_input(&input::CANCEL.to_string());
+    assert_snapshot("playback_mode_seeked_back", &render(&app));
+}
+
+#[test]
+fn help_screen() {
+    let mut app = test_app();
+    app.handle_input(&input::SHOW_HELP.to_string());
+    assert_snapshot("help_screen", &render(&app));
+}
@@ -0,0 +1,82 @@
+//! Transforms between frames for the transports that do not rely on `rustros_tf`.
+
+use crate::transformation::{isometry_to_ros, ros_transform_to_isometry};
+use nalgebra::geometry::Isometry3;
+use rosrust_msg::geometry_msgs::TransformStamped;
+use std::collections::HashMap;
+
+/// Error returned when a transform cannot be looked up.
+#[derive(Debug)]
+pub struct TransformError(pub String);
+
+/// Buffer holding the latest transform of each frame relative to its parent.
+///
+/// Unlike the buffer of `rustros_tf`, it does not interpolate in time: lookups always
+/// return the latest transforms, which is enough to replay data in order.
+#[derive(Default)]
+pub struct TfBuffer {
+    transforms: HashMap<String, TransformStamped>,
+    static_transforms: HashMap<String, TransformStamped>,
+}
+
+fn strip_slash(frame: &str) -> &str {
+    frame.trim_start_matches('/')
+}
+
+impl TfBuffer {
+    /// Adds a transform, replacing the previous one of the same child frame.
+    pub fn add(&mut self, transform: TransformStamped, is_static: bool) {
+        let child = strip_slash(&transform.child_frame_id).to_string();
+        if is_static {
+            self.static_transforms.insert(child, transform);
+        } else {
+            self.transforms.insert(child, transform);
+        }
+    }
+
+    /// Returns true if the buffer has a transform of the frame relative to its parent.
+    pub fn has_frame(&self, frame: &str) -> bool {
+        let frame = strip_slash(frame);
+        self.transforms.contains_key(frame) || self.static_transforms.contains_key(frame)
+    }
+
+    /// Returns the root of the tree of the frame and the pose of the frame in the root.
+    fn pose_in_root<'a>(&'a self, frame: &'a str) -> (&'a str, Isometry3<f64>) {
+        let mut pose = Isometry3::identity();
+        let mut current = strip_slash(frame);
+        // bound the depth, in case the transforms contain a loop
+        for _ in 0..100 {
+            let parent_tf = match self.static_transforms.get(current) {
+                Some(tf) => tf,
+                None => match self.transforms.get(current) {
+                    Some(tf) => tf,
+                    None => break,
+                },
+            };
+            pose = ros_transform_to_isometry(&parent_tf.transform) * pose;
+            current = strip_slash(&parent_tf.header.frame_id);
+        }
+        (current, pose)
+    }
+
+    /// Looks up the latest transform of the frame `to` in the frame `from`.
+    pub fn lookup_transform(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<TransformStamped, TransformError> {
+        let (from_root, from_pose) = self.pose_in_root(from);
+        let (to_root, to_pose) = self.pose_in_root(to);
+        if from_root != to_root {
+            return Err(TransformError(format!(
+                "No transform between {} and {}",
+                from, to
+            )));
+        }
+        let mut transform = TransformStamped::default();
+        transform.header.frame_id = from.to_string();
+        transform.child_frame_id = to.to_string();
+        transform.transform = isometry_to_ros(&(from_pose.inverse() * to_pose));
+        Ok(transform)
+    }
+}
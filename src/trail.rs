@@ -0,0 +1,53 @@
+//! Accumulation of the data of the previous messages of a listener, kept during its decay time.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Data of the messages received during the last seconds, from the oldest to the newest.
+pub struct Trail<T> {
+    decay_time: f64,
+    data: VecDeque<(Instant, T)>,
+}
+
+impl<T> Trail<T> {
+    /// Creates a trail keeping the data during the decay time, in seconds.
+    pub fn new(decay_time: f64) -> Trail<T> {
+        Trail {
+            decay_time: decay_time,
+            data: VecDeque::new(),
+        }
+    }
+
+    /// Returns true if the trail keeps any data.
+    pub fn is_enabled(&self) -> bool {
+        self.decay_time > 0.
+    }
+
+    /// Sets the decay time, in seconds. A decay time of 0 drops the data.
+    pub fn set_decay_time(&mut self, decay_time: f64) {
+        self.decay_time = decay_time;
+        if !self.is_enabled() {
+            self.data.clear();
+        }
+    }
+
+    /// Adds the data of a new message, and drops the data older than the decay time.
+    pub fn push(&mut self, data: T) {
+        let decay_time = self.decay_time;
+        self.data
+            .retain(|(received, _)| received.elapsed().as_secs_f64() <= decay_time);
+        self.data.push_back((Instant::now(), data));
+    }
+
+    /// Returns the data of the messages preceding the newest one which are not older than the
+    /// decay time, with their age as a fraction of the decay time.
+    pub fn previous(&self) -> Vec<(f64, &T)> {
+        let count = self.data.len().saturating_sub(1);
+        self.data
+            .iter()
+            .take(count)
+            .map(|(received, data)| (received.elapsed().as_secs_f64() / self.decay_time, data))
+            .filter(|(age, _)| *age <= 1.)
+            .collect()
+    }
+}
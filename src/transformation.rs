@@ -66,3 +66,19 @@ pub fn ros_transform_to_isometry(tf: &rosrust_msg::geometry_msgs::Transform) ->
     ));
     Isometry3::from_parts(tra, rot)
 }
+
+pub fn isometry_to_ros(iso: &Isometry3<f64>) -> rosrust_msg::geometry_msgs::Transform {
+    rosrust_msg::geometry_msgs::Transform {
+        translation: rosrust_msg::geometry_msgs::Vector3 {
+            x: iso.translation.x,
+            y: iso.translation.y,
+            z: iso.translation.z,
+        },
+        rotation: rosrust_msg::geometry_msgs::Quaternion {
+            x: iso.rotation.quaternion()[0],
+            y: iso.rotation.quaternion()[1],
+            z: iso.rotation.quaternion()[2],
+            w: iso.rotation.quaternion()[3],
+        },
+    }
+}
@@ -0,0 +1,439 @@
+//! Abstraction over the transport of messages and transforms.
+//!
+//! All the listeners and modes talk to ROS through the `Transport` trait, which covers
+//! subscribing, publishing, listing the topics and looking up transforms. Messages cross the
+//! transport in their serialized form, so any backend can carry any message type:
+//! - `RosTransport` is backed by rosrust and needs a running ROS master.
+//! - `MockTransport` is an in-process bus, which allows to run the UI from recorded or
+//!   synthetic data.
+
+use crate::tf::{TfBuffer, TransformError};
+use rosrust_msg::geometry_msgs::TransformStamped;
+use std::any::Any;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Error returned by a transport operation.
+#[derive(Debug)]
+pub struct TransportError(pub String);
+
+/// Name and type of a topic known to the transport.
+#[derive(Debug, Clone)]
+pub struct TopicInfo {
+    pub name: String,
+    pub datatype: String,
+}
+
+/// Description of a message type, needed to advertise a topic.
+#[derive(Debug, Clone)]
+pub struct MessageDescription {
+    pub datatype: String,
+    pub md5sum: String,
+    pub definition: String,
+}
+
+impl MessageDescription {
+    pub fn of<T: rosrust::Message>() -> MessageDescription {
+        MessageDescription {
+            datatype: T::msg_type(),
+            md5sum: T::md5sum(),
+            definition: T::msg_definition(),
+        }
+    }
+}
+
+/// Callback receiving serialized messages.
+pub type RawCallback = Box<dyn Fn(&[u8]) + Send>;
+
+/// Callback shared by the mock transport, so that it is called without holding the bus.
+type SharedCallback = Arc<Mutex<RawCallback>>;
+
+/// Keeps a subscription alive. Dropping it unsubscribes from the topic.
+pub struct Subscription(Box<dyn Any>);
+
+/// Publishes serialized messages on a topic.
+pub trait RawPublisher {
+    fn send_raw(&self, data: Vec<u8>) -> Result<(), TransportError>;
+}
+
+/// Typed publisher on top of a `RawPublisher`.
+pub struct Publisher<T> {
+    inner: Box<dyn RawPublisher>,
+    _msg: PhantomData<T>,
+}
+
+impl<T: rosrust::Message> Publisher<T> {
+    pub fn send(&self, msg: T) -> Result<(), TransportError> {
+        let data = <T as rosrust::RosMsg>::encode_vec(&msg)
+            .map_err(|e| TransportError(format!("Could not serialize message: {:?}", e)))?;
+        self.inner.send_raw(data)
+    }
+}
+
+/// Represents all the operations that termviz needs from ROS.
+pub trait Transport: Send + Sync {
+    /// Subscribes to a topic, calling `callback` with each serialized message.
+    fn subscribe_raw(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        callback: RawCallback,
+    ) -> Result<Subscription, TransportError>;
+
+    /// Advertises a topic with the given message type.
+    fn advertise_raw(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        description: MessageDescription,
+    ) -> Result<Box<dyn RawPublisher>, TransportError>;
+
+    /// Returns all the topics currently known, with their type.
+    fn topics(&self) -> Result<Vec<TopicInfo>, TransportError>;
+
+    /// Looks up the transform of the frame `to` in the frame `from` at the given time.
+    /// A zero time returns the latest available transform.
+    fn lookup_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: rosrust::Time,
+    ) -> Result<TransformStamped, TransformError>;
+}
+
+impl dyn Transport {
+    /// Subscribes to a topic, calling `callback` with each deserialized message.
+    /// Messages that cannot be deserialized are dropped.
+    pub fn subscribe<T, F>(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        callback: F,
+    ) -> Result<Subscription, TransportError>
+    where
+        T: rosrust::Message,
+        F: Fn(T) + Send + 'static,
+    {
+        self.subscribe_raw(
+            topic,
+            queue_size,
+            Box::new(move |data: &[u8]| {
+                if let Ok(msg) = <T as rosrust::RosMsg>::decode_slice(data) {
+                    callback(msg);
+                }
+            }),
+        )
+    }
+
+    /// Advertises a topic for the message type `T`.
+    pub fn advertise<T: rosrust::Message>(
+        &self,
+        topic: &str,
+        queue_size: usize,
+    ) -> Result<Publisher<T>, TransportError> {
+        Ok(Publisher {
+            inner: self.advertise_raw(topic, queue_size, MessageDescription::of::<T>())?,
+            _msg: PhantomData,
+        })
+    }
+}
+
+/// Transport backed by rosrust, requires `rosrust::init` to be called first.
+pub struct RosTransport {
+    tf_listener: rustros_tf::TfListener,
+}
+
+impl RosTransport {
+    pub fn new() -> RosTransport {
+        RosTransport {
+            tf_listener: rustros_tf::TfListener::new(),
+        }
+    }
+}
+
+struct RosRawPublisher(rosrust::Publisher<rosrust::RawMessage>);
+
+impl RawPublisher for RosRawPublisher {
+    fn send_raw(&self, data: Vec<u8>) -> Result<(), TransportError> {
+        self.0
+            .send(rosrust::RawMessage(data))
+            .map_err(|e| TransportError(format!("{:?}", e)))
+    }
+}
+
+impl Transport for RosTransport {
+    fn subscribe_raw(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        callback: RawCallback,
+    ) -> Result<Subscription, TransportError> {
+        let sub = rosrust::subscribe(topic, queue_size, move |msg: rosrust::RawMessage| {
+            callback(&msg.0)
+        })
+        .map_err(|e| TransportError(format!("{:?}", e)))?;
+        Ok(Subscription(Box::new(sub)))
+    }
+
+    fn advertise_raw(
+        &self,
+        topic: &str,
+        queue_size: usize,
+        description: MessageDescription,
+    ) -> Result<Box<dyn RawPublisher>, TransportError> {
+        let publisher = rosrust::publish_with_description(
+            topic,
+            queue_size,
+            rosrust::RawMessageDescription {
+                msg_definition: description.definition,
+                md5sum: description.md5sum,
+                msg_type: description.datatype,
+            },
+        )
+        .map_err(|e| TransportError(format!("{:?}", e)))?;
+        Ok(Box::new(RosRawPublisher(publisher)))
+    }
+
+    fn topics(&self) -> Result<Vec<TopicInfo>, TransportError> {
+        Ok(rosrust::topics()
+            .map_err(|e| TransportError(format!("{:?}", e)))?
+            .into_iter()
+            .map(|topic| TopicInfo {
+                name: topic.name,
+                datatype: topic.datatype,
+            })
+            .collect())
+    }
+
+    fn lookup_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: rosrust::Time,
+    ) -> Result<TransformStamped, TransformError> {
+        self.tf_listener
+            .lookup_transform(from, to, time)
+            .map_err(|e| TransformError(format!("{:?}", e)))
+    }
+}
+
+/// Makes a topic name absolute, as ROS resolves relative names in the root namespace.
+pub fn resolve_topic(topic: &str) -> String {
+    if topic.starts_with('/') {
+        topic.to_string()
+    } else {
+        format!("/{}", topic)
+    }
+}
+
+#[derive(Default)]
+struct MockBus {
+    next_id: usize,
+    topics: HashMap<String, String>,
+    /// The callbacks are called without holding the bus, which allows them to publish or
+    /// subscribe.
+    subscribers: HashMap<String, Vec<(usize, SharedCallback)>>,
+}
+
+/// In-process transport: published messages are delivered synchronously to the subscribers,
+/// and the messages published on `/tf` and `/tf_static` fill its TF buffer.
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    bus: Arc<Mutex<MockBus>>,
+    tf_buffer: Arc<RwLock<TfBuffer>>,
+}
+
+struct MockSubscription {
+    bus: Arc<Mutex<MockBus>>,
+    topic: String,
+    id: usize,
+}
+
+impl Drop for MockSubscription {
+    fn drop(&mut self) {
+        if let Some(callbacks) = self.bus.lock().unwrap().subscribers.get_mut(&self.topic) {
+            callbacks.retain(|(id, _)| *id != self.id);
+        }
+    }
+}
+
+struct MockPublisher {
+    transport: MockTransport,
+    topic: String,
+}
+
+impl RawPublisher for MockPublisher {
+    fn send_raw(&self, data: Vec<u8>) -> Result<(), TransportError> {
+        self.transport.deliver(&self.topic, &data);
+        Ok(())
+    }
+}
+
+impl MockTransport {
+    pub fn new() -> MockTransport {
+        MockTransport::default()
+    }
+
+    /// Makes a topic known to the transport, without publishing anything on it.
+    pub fn add_topic(&self, topic: &str, datatype: &str) {
+        self.bus
+            .lock()
+            .unwrap()
+            .topics
+            .insert(resolve_topic(topic), datatype.to_string());
+    }
+
+    /// Publishes a serialized message of the given type on a topic.
+    pub fn publish_raw(&self, topic: &str, datatype: &str, data: &[u8]) {
+        self.add_topic(topic, datatype);
+        self.deliver(&resolve_topic(topic), data);
+    }
+
+    /// Publishes a message on a topic.
+    pub fn publish<T: rosrust::Message>(&self, topic: &str, msg: &T) {
+        let data = <T as rosrust::RosMsg>::encode_vec(msg).unwrap();
+        self.publish_raw(topic, &T::msg_type(), &data);
+    }
+
+    /// Adds a transform to the TF buffer.
+    pub fn set_transform(&self, transform: TransformStamped, is_static: bool) {
+        self.tf_buffer.write().unwrap().add(transform, is_static);
+    }
+
+    /// Replaces the TF buffer at once, so that lookups never see a partially filled buffer.
+    pub fn replace_transforms(&self, tf_buffer: TfBuffer) {
+        *self.tf_buffer.write().unwrap() = tf_buffer;
+    }
+
+    /// Updates the TF buffer if the serialized message was published on `/tf` or `/tf_static`,
+    /// without delivering it to the subscribers.
+    pub fn update_transforms(&self, topic: &str, data: &[u8]) {
+        let topic = resolve_topic(topic);
+        if topic != "/tf" && topic != "/tf_static" {
+            return;
+        }
+        if let Ok(msg) = <rosrust_msg::tf2_msgs::TFMessage as rosrust::RosMsg>::decode_slice(data) {
+            let mut tf_buffer = self.tf_buffer.write().unwrap();
+            for transform in msg.transforms {
+                tf_buffer.add(transform, topic == "/tf_static");
+            }
+        }
+    }
+
+    fn deliver(&self, topic: &str, data: &[u8]) {
+        self.update_transforms(topic, data);
+        let callbacks: Vec<SharedCallback> = match self.bus.lock().unwrap().subscribers.get(topic) {
+            Some(callbacks) => callbacks
+                .iter()
+                .map(|(_, callback)| callback.clone())
+                .collect(),
+            None => return,
+        };
+        for callback in callbacks {
+            (callback.lock().unwrap())(data);
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    fn subscribe_raw(
+        &self,
+        topic: &str,
+        _queue_size: usize,
+        callback: RawCallback,
+    ) -> Result<Subscription, TransportError> {
+        let topic = resolve_topic(topic);
+        let mut bus = self.bus.lock().unwrap();
+        let id = bus.next_id;
+        bus.next_id += 1;
+        bus.subscribers
+            .entry(topic.clone())
+            .or_insert_with(Vec::new)
+            .push((id, Arc::new(Mutex::new(callback))));
+        Ok(Subscription(Box::new(MockSubscription {
+            bus: self.bus.clone(),
+            topic: topic,
+            id: id,
+        })))
+    }
+
+    fn advertise_raw(
+        &self,
+        topic: &str,
+        _queue_size: usize,
+        description: MessageDescription,
+    ) -> Result<Box<dyn RawPublisher>, TransportError> {
+        let topic = resolve_topic(topic);
+        self.bus
+            .lock()
+            .unwrap()
+            .topics
+            .insert(topic.clone(), description.datatype);
+        Ok(Box::new(MockPublisher {
+            transport: self.clone(),
+            topic: topic,
+        }))
+    }
+
+    fn topics(&self) -> Result<Vec<TopicInfo>, TransportError> {
+        let mut topics: Vec<TopicInfo> = self
+            .bus
+            .lock()
+            .unwrap()
+            .topics
+            .iter()
+            .map(|(name, datatype)| TopicInfo {
+                name: name.clone(),
+                datatype: datatype.clone(),
+            })
+            .collect();
+        topics.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(topics)
+    }
+
+    fn lookup_transform(
+        &self,
+        from: &str,
+        to: &str,
+        _time: rosrust::Time,
+    ) -> Result<TransformStamped, TransformError> {
+        self.tf_buffer.read().unwrap().lookup_transform(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosrust_msg::std_msgs::String as StringMsg;
+
+    fn string_msg(data: &str) -> StringMsg {
+        StringMsg {
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn callbacks_can_subscribe_and_publish() {
+        let mock = MockTransport::new();
+        let transport: Arc<dyn Transport> = Arc::new(mock.clone());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let cb_received = received.clone();
+        let _pong = transport
+            .subscribe("/pong", 1, move |msg: StringMsg| {
+                cb_received.lock().unwrap().push(msg.data)
+            })
+            .unwrap();
+        let cb_mock = mock.clone();
+        let _ping = transport
+            .subscribe("/ping", 1, move |msg: StringMsg| {
+                let transport: Arc<dyn Transport> = Arc::new(cb_mock.clone());
+                let subscription = transport.subscribe("/pong", 1, |_: StringMsg| ());
+                assert!(subscription.is_ok());
+                cb_mock.publish("/pong", &msg);
+            })
+            .unwrap();
+        mock.publish("/ping", &string_msg("hello"));
+        assert_eq!(*received.lock().unwrap(), vec!["hello".to_string()]);
+    }
+}